@@ -2,25 +2,68 @@ use crate::ast::*;
 use anyhow::{Result, anyhow};
 use std::collections::HashMap;
 
+/// Nesting levels of binary operators that get a temp of their own
+///
+/// Temps live at 198 down to 195, between the signal scratch bytes
+/// (192-194) and the accumulator (199).
+const MAX_EXPR_DEPTH: u8 = 4;
+
 pub struct CodeGen {
     bytecode: Vec<u8>,
     variables: HashMap<String, u8>,
     next_var_addr: u8,
     labels: HashMap<String, usize>,
     fixups: Vec<(usize, String)>,
+    expr_depth: u8,
+    origin: usize,
+    energy: Option<f64>,
+    exports: Vec<(String, usize)>,
+}
+
+impl Default for CodeGen {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CodeGen {
     pub fn new() -> Self {
+        Self::with_origin(0)
+    }
+
+    /// Generate code that will be loaded at `origin` instead of address 0
+    pub fn with_origin(origin: usize) -> Self {
         Self {
             bytecode: Vec::new(),
             variables: HashMap::new(),
             next_var_addr: 200, // Variables live in high RAM
             labels: HashMap::new(),
             fixups: Vec::new(),
+            expr_depth: 0,
+            origin,
+            energy: None,
+            exports: Vec::new(),
         }
     }
 
+    /// Energy requested by the first universe declaration
+    pub fn energy(&self) -> Option<f64> {
+        self.energy
+    }
+
+    /// Universe, function and variable addresses
+    pub fn symbols(&self) -> Vec<(String, usize)> {
+        let mut symbols = self.exports.clone();
+        symbols.extend(self.variables.iter().map(|(name, addr)| (name.clone(), *addr as usize)));
+        symbols.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+        symbols
+    }
+
+    /// Address of the next emitted byte
+    fn here(&self) -> usize {
+        self.origin + self.bytecode.len()
+    }
+
     pub fn generate(&mut self, program: Program) -> Result<Vec<u8>> {
         // Find all functions first to allow recursion/forward calls
         // In this simple version, we'll just process statements
@@ -28,11 +71,13 @@ impl CodeGen {
             self.gen_stmt(stmt)?;
         }
 
-        // Apply fixups for jumps/calls
+        // Apply fixups for jumps/calls (16-bit little-endian targets)
         for (offset, label) in &self.fixups {
-            let addr = self.labels.get(label)
+            let addr = *self.labels.get(label)
                 .ok_or_else(|| anyhow!("Undefined label: {}", label))?;
-            self.bytecode[*offset] = *addr as u8;
+            let addr = u16::try_from(addr)
+                .map_err(|_| anyhow!("Label {} at {} is beyond the 16-bit address space", label, addr))?;
+            self.bytecode[*offset..*offset + 2].copy_from_slice(&addr.to_le_bytes());
         }
 
         Ok(self.bytecode.clone())
@@ -40,10 +85,12 @@ impl CodeGen {
 
     fn gen_stmt(&mut self, stmt: Stmt) -> Result<()> {
         match stmt {
-            Stmt::UniverseDecl { name, energy: _, body } => {
+            Stmt::UniverseDecl { name, energy, body } => {
                 // For now, universes are just logical groupings
                 // The body is part of the main entry point
-                self.labels.insert(name, self.bytecode.len());
+                self.energy = self.energy.or(energy);
+                self.exports.push((name.clone(), self.here()));
+                self.labels.insert(name, self.here());
                 for s in body {
                     self.gen_stmt(s)?;
                 }
@@ -51,15 +98,15 @@ impl CodeGen {
             }
             Stmt::FuncDecl { name, params, body } => {
                 // Jump over function body to avoid executing it linearly
-                self.emit_byte(0x10); // JUMP
-                let fixup_idx = self.bytecode.len();
-                self.emit_byte(0); // placeholder for skip_addr
-                
-                let start_addr = self.bytecode.len();
+                let skip_label = format!("func_end_{}", name);
+                self.emit_jump(0x10, None, &skip_label); // JUMP
+
+                let start_addr = self.here();
+                self.exports.push((name.clone(), start_addr));
                 self.labels.insert(name.clone(), start_addr);
                 
                 // Map params to addresses
-                for (_i, param) in params.iter().enumerate() {
+                for param in params.iter() {
                     // Very simple: params pushed to stack before call
                     // We'll pop them into local addresses
                     let addr = self.get_var_addr(param);
@@ -71,19 +118,15 @@ impl CodeGen {
                     self.gen_stmt(s)?;
                 }
                 
+                self.emit_byte(0xFE); // WIDE (16-bit return address)
                 self.emit_byte(0x21); // RET
-                
-                let end_addr = self.bytecode.len();
-                self.bytecode[fixup_idx] = end_addr as u8;
+
+                self.labels.insert(skip_label, self.here());
             }
             Stmt::AssignStmt(name, expr) => {
+                // gen_expr leaves its result in the accumulator (199)
                 self.gen_expr(expr)?;
-                // After evaluating expr, the "result" is in a temporary?
-                // For simplicity, let's assume gen_expr leaves result at address 199
                 let addr = self.get_var_addr(&name);
-                self.emit_byte(0x01); // AtomSet (using 199 as accumulator)
-                // Wait, ISA doesn't have an accumulator. SET needs a literal.
-                // We need a way to COPY from result to variable.
                 self.emit_byte(0x03); // COPY
                 self.emit_byte(199); // src
                 self.emit_byte(addr); // dest
@@ -94,57 +137,42 @@ impl CodeGen {
             }
             Stmt::ReturnStmt(expr) => {
                 self.gen_expr(expr)?;
+                self.emit_byte(0xFE); // WIDE (16-bit return address)
                 self.emit_byte(0x21); // RET
             }
             Stmt::IfStmt { cond, then_block, else_block } => {
-                self.gen_expr(cond)?;
-                self.emit_byte(0x11); // JUMP_IF (accumulator 199 != 0)
-                let then_fixup = self.bytecode.len();
-                self.emit_byte(0); // placeholder for then_addr
-                
-                // Jump over then block if condition is 0
-                // Wait, JUMP_IF is "jump if non-zero". 
-                // We need "jump if zero" or swap blocks.
-                // Let's use a NOP and JUMP for simplicity.
-                
-                // Better: 
                 // 1. Evaluate cond (result in 199)
                 // 2. JUMP_IF 199 to THEN_LABEL
                 // 3. JUMP to ELSE_LABEL or END_LABEL
-                
-                self.emit_byte(0x11); // JUMP_IF
-                self.emit_byte(199);
-                let then_label = format!("if_then_{}", then_fixup);
-                self.fixups.push((self.bytecode.len(), then_label.clone()));
-                self.emit_byte(0); 
+                let if_id = self.bytecode.len();
+                self.gen_expr(cond)?;
+
+                let then_label = format!("if_then_{}", if_id);
+                self.emit_jump(0x11, Some(199), &then_label); // JUMP_IF
 
                 // Jump to Else/End
-                self.emit_byte(0x10); // JMP
-                let else_label = format!("if_else_{}", then_fixup);
-                self.fixups.push((self.bytecode.len(), else_label.clone()));
-                self.emit_byte(0);
+                let else_label = format!("if_else_{}", if_id);
+                self.emit_jump(0x10, None, &else_label); // JMP
 
                 // Then Block
-                self.labels.insert(then_label, self.bytecode.len());
+                self.labels.insert(then_label, self.here());
                 for s in then_block {
                     self.gen_stmt(s)?;
                 }
                 
                 // Jump to End
-                self.emit_byte(0x10); // JMP
-                let end_label = format!("if_end_{}", then_fixup);
-                self.fixups.push((self.bytecode.len(), end_label.clone()));
-                self.emit_byte(0);
+                let end_label = format!("if_end_{}", if_id);
+                self.emit_jump(0x10, None, &end_label); // JMP
 
                 // Else Block
-                self.labels.insert(else_label, self.bytecode.len());
+                self.labels.insert(else_label, self.here());
                 if let Some(eb) = else_block {
                     for s in eb {
                         self.gen_stmt(s)?;
                     }
                 }
                 
-                self.labels.insert(end_label, self.bytecode.len());
+                self.labels.insert(end_label, self.here());
             }
             _ => {} // Implement While later
         }
@@ -166,35 +194,46 @@ impl CodeGen {
                 self.emit_byte(1);
             }
             Expr::BinaryOp(left, op, right) => {
+                // Each nesting level keeps its left operand in its own temp
+                // (198, 197, ...) so the right-hand side cannot clobber it
+                if self.expr_depth >= MAX_EXPR_DEPTH {
+                    return Err(anyhow!("Expression nests more than {} operators deep", MAX_EXPR_DEPTH));
+                }
+                let l = 198 - self.expr_depth;
+                self.expr_depth += 1;
                 self.gen_expr(*left)?;
-                // Move L to temp 198
-                self.emit_byte(0x03);
-                self.emit_byte(199);
-                self.emit_byte(198);
-                self.emit_byte(1);
-                
-                self.gen_expr(*right)?;
+                self.emit(&[0x03, 199, l, 1]); // COPY L -> temp
+
+                let result = self.gen_expr(*right);
+                self.expr_depth -= 1;
+                result?;
                 // R is in 199
-                
+
                 match op {
-                    Op::Add => {
-                        self.emit_byte(0x04); // ADD
-                        self.emit_byte(199);  // dest
-                        self.emit_byte(198);  // src
+                    Op::Add => self.emit(&[0x04, 199, l]), // ADD: 199 = R + L
+                    Op::Mul => self.emit(&[0x60, 199, l]), // MUL: 199 = R * L
+                    Op::Sub | Op::Div => {
+                        let opcode = if matches!(op, Op::Sub) { 0x05 } else { 0x61 };
+                        self.emit(&[opcode, l, 199]); // SUB/DIV: L = L op R
+                        self.emit(&[0x03, l, 199, 1]); // COPY result -> 199
+                    }
+                    // Comparisons produce a boolean (0/1) in 199
+                    Op::Eq | Op::Ne => {
+                        self.emit(&[0x06, l, 199, 199]); // CMP: 0 iff equal
+                        self.emit(&[0x09, 199, 0, 199]); // CMPI: 1 iff not equal
+                        if matches!(op, Op::Eq) {
+                            self.emit(&[0x02, 199, 1]); // XOR: invert
+                        }
                     }
-                    Op::Sub => {
-                        self.emit_byte(0x05); // SUB (199 = 199 - 198) -- wait, we want 198 - 199
-                        // Swapping src/dest or using temp
-                        self.emit_byte(0x05); 
-                        self.emit_byte(198);
-                        self.emit_byte(199);
-                        // Result in 198, move to 199
-                        self.emit_byte(0x03);
-                        self.emit_byte(198);
-                        self.emit_byte(199);
-                        self.emit_byte(1);
+                    Op::Lt | Op::Ge | Op::Gt | Op::Le => {
+                        // CMP yields 255 for "less", whose top bit is the answer
+                        let (a, b) = if matches!(op, Op::Lt | Op::Ge) { (l, 199) } else { (199, l) };
+                        self.emit(&[0x06, a, b, 199]); // CMP
+                        self.emit(&[0x66, 199, 7]); // SHR 7: 1 iff a < b
+                        if matches!(op, Op::Ge | Op::Le) {
+                            self.emit(&[0x02, 199, 1]); // XOR: invert
+                        }
                     }
-                    _ => {} // Implement others
                 }
             }
             Expr::Call(name, args) => {
@@ -204,31 +243,38 @@ impl CodeGen {
                     self.emit_byte(0x22); // PUSH
                     self.emit_byte(199);
                 }
-                self.emit_byte(0x20); // CALL
-                self.fixups.push((self.bytecode.len(), name));
-                self.emit_byte(0); // placeholder
+                self.emit_jump(0x20, None, &name); // CALL
             }
             Expr::Signal(target, data) => {
-                self.gen_expr(*target)?;
-                self.emit_byte(0x03);
-                self.emit_byte(199);
-                self.emit_byte(197); // Target temp
-                self.emit_byte(1);
+                // The target id lives in memory (u16 LE at 193) so it can be computed
+                match *target {
+                    Expr::Number(n) => {
+                        let id = u16::try_from(n as u64)
+                            .map_err(|_| anyhow!("Signal target {} is not a valid universe id", n))?;
+                        let [lo, hi] = id.to_le_bytes();
+                        self.emit(&[0x40, 193, lo, hi]); // SET16
+                    }
+                    target => {
+                        self.gen_expr(target)?;
+                        self.emit(&[0x03, 199, 193, 1]); // COPY id -> 193
+                        self.emit(&[0x01, 194, 0]); // SET high byte
+                    }
+                }
 
-                self.gen_expr(*data)?;
-                // Signal needs: SIGNAL [target] [len] [data...]
-                // Our ISA CURRENTLY takes literal len and data.
-                // This is hard for dynamic data. 
-                // Let's emit a fixed-size signal for now if it's a number.
-                self.emit_byte(0xF0); // SIGNAL
-                self.emit_byte(127);  // Use a temporary target variable address? 
-                // No, ISA::step takes target_id from state[ip+1].
-                // This means the TARGET MUST BE A LITERAL in the current ISA.
-                // We should probably update the ISA to handle dynamic targets,
-                // but for v1, we'll assume target is a literal.
-                self.emit_byte(2); // placeholder target
-                self.emit_byte(1); // len
-                self.emit_byte(0); // placeholder for data byte
+                match *data {
+                    Expr::String(s) => {
+                        let text = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(&s);
+                        let len = u8::try_from(text.len())
+                            .map_err(|_| anyhow!("Signal payload longer than 255 bytes"))?;
+                        self.emit(&[0xF6, 193, len]); // SIGNAL @193 "text"
+                        self.emit(text.as_bytes());
+                    }
+                    data => {
+                        self.gen_expr(data)?;
+                        self.emit(&[0x01, 192, 1]); // SET len = 1
+                        self.emit(&[0xF7, 193, 199, 192]); // SIGNALI target buf len
+                    }
+                }
             }
             _ => {}
         }
@@ -246,4 +292,51 @@ impl CodeGen {
     fn emit_byte(&mut self, b: u8) {
         self.bytecode.push(b);
     }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.bytecode.extend_from_slice(bytes);
+    }
+
+    /// Emit a WIDE control-flow instruction (JUMP/JUMP_IF/CALL) to a label
+    ///
+    /// Targets are 16-bit so programs are not limited to 256 bytes of code.
+    fn emit_jump(&mut self, opcode: u8, cond_addr: Option<u8>, label: &str) {
+        self.emit_byte(0xFE); // WIDE
+        self.emit_byte(opcode);
+        if let Some(addr) = cond_addr {
+            self.emit_byte(addr);
+            self.emit_byte(0);
+        }
+        self.fixups.push((self.bytecode.len(), label.to_string()));
+        self.emit_byte(0); // placeholder (low byte)
+        self.emit_byte(0); // placeholder (high byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile;
+
+    #[test]
+    fn test_arithmetic_lowering() {
+        // The left operand is parked in temp 198 while the right one is evaluated
+        assert_eq!(compile("universe u { a = 7 * 3; }").unwrap(), vec![
+            0x01, 199, 7,       // SET 199 7
+            0x03, 199, 198, 1,  // COPY 199 198 1
+            0x01, 199, 3,       // SET 199 3
+            0x60, 199, 198,     // MUL 199 198
+            0x03, 199, 200, 1,  // COPY 199 a 1
+            0xFF,
+        ]);
+        // DIV works in the temp and copies the quotient back
+        assert_eq!(compile("b = 9 / 2;").unwrap()[10..], [0x61, 198, 199, 0x03, 198, 199, 1, 0x03, 199, 200, 1]);
+    }
+
+    #[test]
+    fn test_comparison_lowering() {
+        // a > b is CMP b a, whose "less" result 255 shifts down to 1
+        assert_eq!(compile("c = 5 > 2;").unwrap()[10..], [0x06, 199, 198, 199, 0x66, 199, 7, 0x03, 199, 200, 1]);
+        // a == b turns the CMP result into 0/1 and inverts it
+        assert_eq!(compile("d = 5 == 2;").unwrap()[10..], [0x06, 198, 199, 199, 0x09, 199, 0, 199, 0x02, 199, 1, 0x03, 199, 200, 1]);
+    }
 }
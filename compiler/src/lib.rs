@@ -4,6 +4,13 @@ pub mod parser;
 pub mod codegen;
 
 use anyhow::Result;
+use paradoxef::{ProgramImage, Section, SectionKind, Symbol};
+
+/// Load address of code in a compiled image
+///
+/// Everything below is the zero page holding variables and expression
+/// temporaries, which therefore can no longer collide with the code.
+pub const IMAGE_CODE_BASE: u16 = 256;
 
 pub fn compile(source: &str) -> Result<Vec<u8>> {
     let mut parser = parser::Parser::new(source);
@@ -13,6 +20,27 @@ pub fn compile(source: &str) -> Result<Vec<u8>> {
     codegen.generate(program)
 }
 
+/// Compile to a ParadoxEF image with code above a zero-filled data page
+pub fn compile_image(source: &str) -> Result<ProgramImage> {
+    let mut parser = parser::Parser::new(source);
+    let program = parser.parse()?;
+
+    let mut codegen = codegen::CodeGen::with_origin(IMAGE_CODE_BASE as usize);
+    let code = codegen.generate(program)?;
+    Ok(ProgramImage {
+        entry: IMAGE_CODE_BASE,
+        stack_size: 0,
+        energy: codegen.energy().unwrap_or(0.0),
+        sections: vec![
+            Section { kind: SectionKind::Bss, addr: 0, data: vec![0; IMAGE_CODE_BASE as usize] },
+            Section { kind: SectionKind::Code, addr: IMAGE_CODE_BASE, data: code },
+        ],
+        symbols: codegen.symbols().into_iter()
+            .map(|(name, addr)| Symbol { name, addr: addr as u16 })
+            .collect(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,4 +59,43 @@ mod tests {
         let bytecode = compile(source).unwrap();
         assert!(!bytecode.is_empty());
     }
+
+    #[test]
+    fn test_signal_target_from_memory() {
+        let bytecode = compile(r#"signal(999, "hi");"#).unwrap();
+        // SET16 193 999; SIGNAL @193 "hi"
+        assert_eq!(bytecode, vec![0x40, 193, 0xE7, 0x03, 0xF6, 193, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn test_expression_depth_limit() {
+        assert!(compile("x = 1 + 2 + 3 + 4 + 5;").is_ok());
+        let err = compile("x = 1 + 2 + 3 + 4 + 5 + 6;").unwrap_err();
+        assert!(err.to_string().contains("nests more than 4 operators deep"));
+    }
+
+    #[test]
+    fn test_compile_image() {
+        let image = compile_image(r#"
+            universe counter {
+                energy: 40.0;
+                func bump(x) {
+                    return x + 1;
+                }
+                total = bump(1);
+            }
+        "#).unwrap();
+
+        assert_eq!(image.energy, 40.0);
+        assert_eq!(image.entry, IMAGE_CODE_BASE);
+        assert_eq!(image.symbol("counter"), Some(IMAGE_CODE_BASE));
+        assert!(image.symbol("bump").unwrap() > IMAGE_CODE_BASE);
+        assert!(image.symbol("total").unwrap() < IMAGE_CODE_BASE);
+
+        // Jump targets are relocated past the zero page
+        let code = &image.section(SectionKind::Code).unwrap().data;
+        assert_eq!(&code[..2], &[0xFE, 0x10]);
+        assert!(u16::from_le_bytes([code[2], code[3]]) > IMAGE_CODE_BASE);
+        assert!(image.layout().is_ok());
+    }
 }
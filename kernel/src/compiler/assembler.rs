@@ -2,215 +2,1233 @@
 //!
 //! Supports:
 //! - Human-readable mnemonics (SET, ADD, etc.)
+//! - Wide addressing via the `.W` mnemonic suffix (JUMP.W far_label)
 //! - Labels (start:)
 //! - Definitions (.def name value)
+//! - Registers (r0-r7) for the register-file instructions
+//! - Width-suffixed integer instructions (ADD16, CMPS32...) with signed literals
+//! - Memory-indirect targets via `@addr` (SIGNAL @child "hello")
+//! - Payload literals: strings with escapes ("line\n", "\x00", "\"") and
+//!   byte arrays ([0x01, 0xFF])
+//! - Sections (.text, .rodata, .data, .bss) with .byte, .word, .ascii,
+//!   .zero and .org
+//! - Image header directives (.entry label, .stack depth, .energy joules)
+//! - Constant expressions in operands (queue_start + 5, (end - start) / 2)
+//! - Includes and macros (see `preprocessor`)
+//! - Relocatable objects (.global, .extern) for `paradoxef::link`
 //! - Comments (# or //)
+//!
+//! Operands are separated by whitespace, so in expressions a `-` or `+`
+//! written against the following term but apart from the previous one
+//! starts a new operand: `SET16 44 -2` has two operands, `SET x - 2` one.
+//!
+//! Errors are collected across the whole source and reported together as
+//! `AsmDiagnostic`s with line and column.
+
+use crate::universe::isa::{Operand, OpCode, REGISTER_COUNT};
+use super::diagnostic::{span_offset, AsmDiagnostic, AsmError, Severity};
+use super::preprocessor::{is_comment, preprocess, Line};
+use paradoxef::{ObjectFile, ObjectSymbol, ProgramImage, Relocation, RelocationKind, Section, SectionKind, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Map a mnemonic (without `.W` suffix) to its opcode
+fn lookup_mnemonic(mnemonic: &str) -> Option<OpCode> {
+    match mnemonic {
+        "JMP" => Some(OpCode::Jump),
+        "JIF" | "JNZ" => Some(OpCode::JumpIf),
+        // Indirect forms are selected by `@` operands and WIDE by `.W`
+        _ => (0..=u8::MAX)
+            .filter_map(OpCode::from_u8)
+            .filter(|op| !op.is_indirect() && *op != OpCode::Wide)
+            .find(|op| op.mnemonic() == mnemonic),
+    }
+}
+
+/// Split a mnemonic into its opcode and wide flag (`SET.W` -> AtomSet, true)
+fn parse_mnemonic(word: &str) -> Option<(OpCode, bool)> {
+    let upper = word.to_uppercase();
+    match upper.strip_suffix(".W") {
+        Some(base) => lookup_mnemonic(base).map(|op| (op, true)),
+        None => lookup_mnemonic(&upper).map(|op| (op, false)),
+    }
+}
+
+/// Check if a word is an instruction mnemonic
+pub(crate) fn is_mnemonic(word: &str) -> bool {
+    parse_mnemonic(word).is_some()
+}
+
+/// Indirect form selected by an `@addr` first operand
+fn indirect_form(op: OpCode) -> Option<OpCode> {
+    match op {
+        OpCode::Signal => Some(OpCode::SignalTo),
+        OpCode::Entangle => Some(OpCode::EntangleInd),
+        OpCode::Observe => Some(OpCode::ObserveInd),
+        OpCode::Branch => Some(OpCode::BranchInd),
+        _ => None,
+    }
+}
+
+/// Select the opcode and wide flag for a source line's words
+fn parse_instruction<'a>(parts: &[&'a str]) -> LineResult<'a, (OpCode, bool)> {
+    let (op, wide) = parse_mnemonic(parts[0])
+        .ok_or_else(|| LineError::new(parts[0], format!("unknown opcode '{}'", parts[0])))?;
+    match parts.get(1) {
+        Some(arg) if arg.starts_with('@') => indirect_form(op)
+            .map(|op| (op, wide))
+            .ok_or_else(|| LineError::new(arg, format!("{} does not take an indirect '@' operand", parts[0]))),
+        _ => Ok((op, wide)),
+    }
+}
+
+/// Parse a register name (`r0`-`r7`) into its index
+fn parse_register(arg: &str) -> Option<usize> {
+    let index = arg.strip_prefix(['r', 'R'])?.parse::<usize>().ok()?;
+    (index < REGISTER_COUNT).then_some(index)
+}
+
+/// Section selected by a directive (`.text`, `.rodata`, `.data`, `.bss`)
+fn section_directive(word: &str) -> Option<SectionKind> {
+    match word {
+        ".text" => Some(SectionKind::Code),
+        ".rodata" => Some(SectionKind::Rodata),
+        ".data" => Some(SectionKind::Data),
+        ".bss" => Some(SectionKind::Bss),
+        _ => None,
+    }
+}
+
+/// Directives that emit or reserve bytes in the current section
+const DATA_DIRECTIVES: [&str; 5] = [".byte", ".word", ".ascii", ".zero", ".org"];
 
-use crate::universe::isa::OpCode;
-use std::collections::HashMap;
+/// Directives that set image header fields
+const HEADER_DIRECTIVES: [&str; 3] = [".entry", ".stack", ".energy"];
+
+/// Directives that share symbols between objects
+const LINK_DIRECTIVES: [&str; 2] = [".global", ".extern"];
+
+/// Name reported for sources that do not come from a file
+const ANONYMOUS_SOURCE: &str = "<input>";
 
 /// Assemble source code into bytecode
-pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
-    let mut labels = HashMap::new();
-    let mut definitions = HashMap::new();
-    let mut byte_offset = 0;
-    
-    // ==========================================
-    // Pass 1: Symbol Discovery and Offset Calculation
-    // ==========================================
-    for (line_num, line) in source.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") {
-            continue;
+///
+/// Plain programs (code only, entered at 0) come out as raw bytecode.
+/// Programs using sections or header directives come out as a serialized
+/// ParadoxEF image; `Kernel::load_program` accepts both.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    image_bytes(ANONYMOUS_SOURCE, assemble_image(source)?)
+}
+
+/// Assemble a source file into bytecode, resolving includes next to it
+pub fn assemble_file(path: impl AsRef<Path>) -> Result<Vec<u8>, AsmError> {
+    let path = path.as_ref();
+    let file = path.display().to_string();
+    let source = std::fs::read_to_string(path).map_err(|e| AsmError {
+        diagnostics: vec![AsmDiagnostic::file_level(Severity::Error, &file, format!("cannot read source: {}", e))],
+    })?;
+    image_bytes(&file, assemble_image_named(&file, &source)?)
+}
+
+/// Raw bytecode for plain programs, a serialized image otherwise
+fn image_bytes(file: &str, mut image: ProgramImage) -> Result<Vec<u8>, AsmError> {
+    let plain = image.sections.len() == 1 && image.entry == 0
+        && image.stack_size == 0 && image.energy == 0.0;
+    if plain {
+        return Ok(image.sections.remove(0).data);
+    }
+    image.to_bytes().map_err(|e| AsmError {
+        diagnostics: vec![AsmDiagnostic::file_level(Severity::Error, file, format!("cannot write image: {}", e))],
+    })
+}
+
+/// Assemble source code into a program image
+///
+/// Sections are placed one after another in the order code, rodata, data,
+/// bss, starting at address 0. Includes are resolved against the current
+/// directory.
+pub fn assemble_image(source: &str) -> Result<ProgramImage, AsmError> {
+    assemble_image_named(ANONYMOUS_SOURCE, source)
+}
+
+/// Assemble source code read from `file` into a program image
+///
+/// `file` names the source in diagnostics, and includes are resolved
+/// relative to its directory.
+pub fn assemble_image_named(file: &str, source: &str) -> Result<ProgramImage, AsmError> {
+    match run(file, source, false) {
+        (Some(Output::Image(image)), _) => Ok(image),
+        (_, diagnostics) => Err(AsmError { diagnostics }),
+    }
+}
+
+/// Assemble source code read from `file` into a relocatable object
+///
+/// Sections start at offset 0 and every operand naming a label or an
+/// `.extern` symbol becomes a relocation, so objects can be combined with
+/// `paradoxef::link`. Labels listed in `.global` are visible to other
+/// objects, and `.entry` must name a label.
+pub fn assemble_object(file: &str, source: &str) -> Result<ObjectFile, AsmError> {
+    match run(file, source, true) {
+        (Some(Output::Object(object)), _) => Ok(object),
+        (_, diagnostics) => Err(AsmError { diagnostics }),
+    }
+}
+
+/// Assemble source code and return every error and warning found
+pub fn check(file: &str, source: &str) -> Vec<AsmDiagnostic> {
+    run(file, source, false).1
+}
+
+/// Result of a successful assembly
+enum Output {
+    Image(ProgramImage),
+    Object(ObjectFile),
+}
+
+/// Preprocess and assemble, collecting diagnostics instead of stopping at
+/// the first error
+fn run(file: &str, source: &str, object: bool) -> (Option<Output>, Vec<AsmDiagnostic>) {
+    let preprocessed = preprocess(file, source);
+    let mut assembler = Assembler::new(file, object);
+    assembler.diagnostics = preprocessed.diagnostics;
+    let lines: Vec<SourceLine<'_>> = preprocessed.lines.iter().enumerate()
+        .filter_map(|(index, line)| SourceLine::parse(index, line))
+        .collect();
+    assembler.run(&lines)
+}
+
+/// A source line holding a label, directive or instruction
+struct SourceLine<'a> {
+    /// Position in the preprocessed source
+    index: usize,
+    line: &'a Line,
+    trimmed: &'a str,
+    /// First word, then operands (an expression may span several words)
+    parts: Vec<&'a str>,
+}
+
+impl<'a> SourceLine<'a> {
+    /// Split a line into words, skipping blank lines and comments
+    fn parse(index: usize, line: &'a Line) -> Option<Self> {
+        let trimmed = line.text.trim();
+        if trimmed.is_empty() || is_comment(trimmed) {
+            return None;
+        }
+        let words = split_words(trimmed);
+        if words.is_empty() {
+            return None;
         }
+        let mut parts = vec![words[0]];
+        parts.extend(group_operands(trimmed, &words[1..]));
+        Some(Self { index, line, trimmed, parts })
+    }
+
+    /// Text from the start of `first` to the end of `last`
+    fn span(&self, first: &str, last: &str) -> &'a str {
+        let start = span_offset(self.trimmed, first).unwrap_or(0);
+        let end = span_offset(self.trimmed, last).map_or(self.trimmed.len(), |offset| offset + last.len());
+        &self.trimmed[start..end.max(start)]
+    }
+}
+
+/// Split a line into words up to a trailing comment
+///
+/// A string (`"..."`) or byte array (`[...]`) is one word even if it holds
+/// spaces or comment markers.
+fn split_words(trimmed: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut rest = trimmed.trim_start();
+    while !rest.is_empty() && !is_comment(rest) {
+        let mut end = 0;
+        let mut closing = None;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            end = i + c.len_utf8();
+            match closing {
+                Some(_) if escaped => escaped = false,
+                Some('"') if c == '\\' => escaped = true,
+                Some(close) if c == close => closing = None,
+                Some(_) => {}
+                None if c.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                None if c == '"' => closing = Some('"'),
+                None if c == '[' => closing = Some(']'),
+                None => {}
+            }
+        }
+        words.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    words
+}
+
+/// Join the words of a line into operands, keeping expressions together
+fn group_operands<'a>(trimmed: &'a str, words: &[&'a str]) -> Vec<&'a str> {
+    let continues = |word: &str| word.ends_with(['+', '-', '*', '/', '(']);
+    let joins = |word: &str| word.starts_with(['*', '/', ')']) || word == "+" || word == "-";
+    let literal = |word: &str| word.starts_with(['"', '[']);
+
+    let mut operands: Vec<&'a str> = Vec::new();
+    for word in words {
+        let start = span_offset(trimmed, word).unwrap_or(0);
+        match operands.last_mut() {
+            Some(last) if !literal(last) && !literal(word) && (continues(last) || joins(word)) => {
+                let last_start = span_offset(trimmed, last).unwrap_or(0);
+                *last = &trimmed[last_start..start + word.len()];
+            }
+            _ => operands.push(word),
+        }
+    }
+    operands
+}
+
+/// An error at one word of the line being assembled
+struct LineError<'a> {
+    span: &'a str,
+    message: String,
+}
+
+impl<'a> LineError<'a> {
+    fn new(span: &'a str, message: impl Into<String>) -> Self {
+        Self { span, message: message.into() }
+    }
+}
+
+type LineResult<'a, T> = Result<T, LineError<'a>>;
+
+/// Two-pass assembler state
+struct Assembler<'a> {
+    file: &'a str,
+    /// Emitting a relocatable object rather than an image
+    object: bool,
+    /// Diagnostics with the position of the line they concern
+    diagnostics: Vec<(usize, AsmDiagnostic)>,
+    definitions: HashMap<&'a str, i64>,
+    /// Label -> (section, offset in section, defining line)
+    labels: HashMap<&'a str, (SectionKind, usize, &'a Line)>,
+    /// Label addresses, known once sections are placed
+    addresses: HashMap<&'a str, usize>,
+    /// Labels exported with `.global`
+    globals: HashSet<&'a str>,
+    /// Symbols declared with `.extern`, in declaration order
+    externs: Vec<&'a str>,
+    /// Label named by `.entry` in an object
+    entry: Option<&'a str>,
+    relocations: Vec<Relocation>,
+    section: SectionKind,
+    sizes: [usize; 4],
+    /// Section start addresses (all 0 in objects)
+    bases: [usize; 4],
+    output: [Vec<u8>; 4],
+    image: ProgramImage,
+}
+
+impl<'a> Assembler<'a> {
+    fn new(file: &'a str, object: bool) -> Self {
+        Self {
+            file,
+            object,
+            diagnostics: Vec::new(),
+            definitions: HashMap::new(),
+            labels: HashMap::new(),
+            addresses: HashMap::new(),
+            globals: HashSet::new(),
+            externs: Vec::new(),
+            entry: None,
+            relocations: Vec::new(),
+            section: SectionKind::Code,
+            sizes: [0; 4],
+            bases: [0; 4],
+            output: Default::default(),
+            image: ProgramImage::from_code(Vec::new()),
+        }
+    }
+
+    fn run(mut self, lines: &[SourceLine<'a>]) -> (Option<Output>, Vec<AsmDiagnostic>) {
+        // ==========================================
+        // Pass 1: Symbol Discovery and Offset Calculation
+        // ==========================================
+        // Lines that fail here are not encoded, so each problem is reported once
+        let mut failed = HashSet::new();
+        for line in lines {
+            if let Err(err) = self.measure(line) {
+                self.report(line, Severity::Error, err);
+                failed.insert(line.index);
+            }
+        }
+
+        // Sections follow each other from address 0; in objects the linker
+        // places them
+        let mut next = 0;
+        for kind in SectionKind::ALL {
+            if !self.object {
+                self.bases[kind as usize] = next;
+            }
+            next += self.sizes[kind as usize];
+        }
+        if next > paradoxef::ADDRESS_SPACE {
+            self.diagnostics.push((usize::MAX, AsmDiagnostic::file_level(
+                Severity::Error,
+                self.file,
+                format!("program needs {} bytes, more than the 16-bit address space", next),
+            )));
+        }
+        self.addresses = self.labels.iter()
+            .map(|(name, (kind, offset, _))| (*name, self.bases[*kind as usize] + offset))
+            .collect();
+
+        // ==========================================
+        // Pass 2: Code Generation
+        // ==========================================
+        self.section = SectionKind::Code;
+        for line in lines.iter().filter(|line| !failed.contains(&line.index)) {
+            if let Err(err) = self.encode(line) {
+                self.report(line, Severity::Error, err);
+            }
+        }
+
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        diagnostics.sort_by_key(|(index, d)| (*index, d.column));
+        let diagnostics: Vec<AsmDiagnostic> = diagnostics.into_iter().map(|(_, d)| d).collect();
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return (None, diagnostics);
+        }
+        (Some(self.finish()), diagnostics)
+    }
+
+    fn finish(self) -> Output {
+        let sections = SectionKind::ALL.into_iter()
+            .zip(self.output)
+            .filter(|(kind, data)| *kind == SectionKind::Code || !data.is_empty())
+            .map(|(kind, data)| Section { kind, addr: self.bases[kind as usize] as u16, data })
+            .collect();
+
+        if !self.object {
+            let mut image = self.image;
+            image.sections = sections;
+            image.symbols = self.addresses.into_iter()
+                .map(|(name, addr)| Symbol { name: name.to_string(), addr: addr as u16 })
+                .collect();
+            image.symbols.sort_by(|a, b| (a.addr, &a.name).cmp(&(b.addr, &b.name)));
+            return Output::Image(image);
+        }
+
+        let mut symbols: Vec<ObjectSymbol> = self.labels.iter()
+            .map(|(name, (section, offset, _))| ObjectSymbol {
+                name: name.to_string(),
+                section: *section,
+                offset: *offset as u16,
+                global: self.globals.contains(name),
+            })
+            .collect();
+        symbols.sort_by(|a, b| (a.section as u8, a.offset, &a.name).cmp(&(b.section as u8, b.offset, &b.name)));
+        Output::Object(ObjectFile {
+            name: self.file.to_string(),
+            entry: self.entry.map(str::to_string),
+            stack_size: self.image.stack_size,
+            energy: self.image.energy,
+            sections,
+            symbols,
+            imports: self.externs.iter().map(|name| name.to_string()).collect(),
+            relocations: self.relocations,
+        })
+    }
+
+    fn report(&mut self, line: &SourceLine<'a>, severity: Severity, err: LineError<'a>) {
+        let source = line.line;
+        let message = match &source.expanded_from {
+            Some(name) => format!("{} (in macro '{}')", err.message, name),
+            None => err.message,
+        };
+        let diagnostic = AsmDiagnostic::at(severity, &source.file, source.number, &source.text, err.span, message);
+        self.diagnostics.push((line.index, diagnostic));
+    }
+
+    /// Pass 1: record definitions and labels, and size the line
+    fn measure(&mut self, line: &SourceLine<'a>) -> LineResult<'a, ()> {
+        let parts = &line.parts;
 
         // Handle Definitions: .def name value
-        if trimmed.starts_with(".def") {
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() < 3 { return Err(format!("Line {}: .def requires name and value", line_num)); }
-            let val = parts[2].parse::<u8>().map_err(|_| format!("Line {}: Invalid def value", line_num))?;
-            definitions.insert(parts[1].to_string(), val);
-            continue;
+        if parts[0] == ".def" {
+            expect_operands(parts, 2)?;
+            let value = self.constant(parts[2])?;
+            self.definitions.insert(parts[1], value);
+            return Ok(());
         }
 
         // Handle Labels: name:
-        if trimmed.ends_with(':') {
-            let label_name = &trimmed[..trimmed.len()-1]; // Remove trailing colon
-            labels.insert(label_name.to_string(), byte_offset);
-            continue;
-        }
-
-        let parts: Vec<&str> = trimmed.split_whitespace().collect();
-        if parts.is_empty() { continue; }
-
-        match parts[0].to_uppercase().as_str() {
-            "NOP" | "RET" | "HALT" => byte_offset += 1,
-            "PUSH" | "POP" | "JUMP" | "JMP" | "CALL" | "REVERT" | "MEMSWAP" => byte_offset += 2,
-            "SET" | "XOR" | "ADD" | "SUB" | "JUMPIF" | "JIF" | "JNZ" | "ENTANGLE" | "BRANCH" | "MEMALLOC" | "MEMMAP" => byte_offset += 3,
-            "COPY" | "CMP" | "SIGNAL" | "OBSERVE" => {
-               if parts[0].eq_ignore_ascii_case("SIGNAL") {
-                    // SIGNAL target "message"
-                     // OpCode + Target + Len + Payload
-                     let rest = trimmed.splitn(3, ' ').nth(2).unwrap_or("");
-                     let payload_len = if rest.starts_with('"') && rest.ends_with('"') {
-                         rest.len() - 2 // Quotes
-                     } else {
-                         rest.len()
-                     };
-                     byte_offset += 3 + payload_len;
-               } else {
-                   // COPY/CMP have 3 args + opcode
-                   byte_offset += 4;
-               }
-            },
-            _ => return Err(format!("Line {}: Unknown opcode '{}'", line_num, parts[0])),
-        }
-    }
-
-    // ==========================================
-    // Pass 2: Code Generation
-    // ==========================================
-    let mut bytecode = Vec::new();
-
-    // Helper to resolve arguments (number, def, or label)
-    let resolve_arg = |arg: &str, ln: usize| -> Result<u8, String> {
-        // Try direct number
-        if let Ok(val) = arg.parse::<u8>() {
-             return Ok(val);
-        }
-        // Try definitions
-        if let Some(val) = definitions.get(arg) {
-            return Ok(*val);
-        }
-        // Try labels
-        if let Some(offset) = labels.get(arg) {
-            return Ok(*offset as u8);
-        }
-        
-        Err(format!("Line {}: Unknown symbol '{}'", ln, arg))
-    };
+        if let Some(name) = line.trimmed.strip_suffix(':') {
+            if parts.len() != 1 || name.is_empty() {
+                return Err(LineError::new(line.trimmed, format!("invalid label '{}'", name)));
+            }
+            symbol_name(name)?;
+            if let Some((_, _, first)) = self.labels.get(name) {
+                let place = if first.file == line.line.file { String::new() } else { format!(" of {}", first.file) };
+                return Err(LineError::new(name, format!("label '{}' is already defined on line {}{}", name, first.number, place)));
+            }
+            self.labels.insert(name, (self.section, self.sizes[self.section as usize], line.line));
+            return Ok(());
+        }
 
-    for (line_num, line) in source.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") 
-           || trimmed.starts_with(".def") || trimmed.ends_with(':') {
-            continue;
-        }
-
-        let parts: Vec<&str> = trimmed.split_whitespace().collect();
-        match parts[0].to_uppercase().as_str() {
-            "NOP" => bytecode.push(OpCode::NoOp as u8),
-            "SET" => {
-                bytecode.push(OpCode::AtomSet as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "XOR" => {
-                bytecode.push(OpCode::AtomXor as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "COPY" => {
-                bytecode.push(OpCode::AtomCopy as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-                bytecode.push(resolve_arg(parts[3], line_num)?);
-            },
-            "ADD" => {
-                bytecode.push(OpCode::Add as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "SUB" => {
-                bytecode.push(OpCode::Sub as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "CMP" => {
-                bytecode.push(OpCode::Cmp as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-                bytecode.push(resolve_arg(parts[3], line_num)?);
-            },
-            "JUMP" | "JMP" => {
-                bytecode.push(OpCode::Jump as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "JUMPIF" | "JIF" | "JNZ" => {
-                bytecode.push(OpCode::JumpIf as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?); // This can resolve label!
-            },
-            "CALL" => {
-                bytecode.push(OpCode::Call as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "RET" => bytecode.push(OpCode::Ret as u8),
-            "PUSH" => {
-                bytecode.push(OpCode::Push as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "POP" => {
-                bytecode.push(OpCode::Pop as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "SIGNAL" => {
-                bytecode.push(OpCode::Signal as u8);
-                
-                let target = resolve_arg(parts[1], line_num)?;
-                bytecode.push(target);
-                
-                let rest_of_line = trimmed.splitn(3, ' ').nth(2).unwrap_or("");
-                let payload = if rest_of_line.starts_with('"') && rest_of_line.ends_with('"') {
-                    rest_of_line[1..rest_of_line.len()-1].as_bytes()
-                } else {
-                    rest_of_line.as_bytes()
-                };
+        if let Some(kind) = section_directive(parts[0]) {
+            self.section = kind;
+            return Ok(());
+        }
+        if parts[0] == ".extern" {
+            expect_operands(parts, 1)?;
+            for name in &parts[1..] {
+                symbol_name(name)?;
+                if !self.externs.contains(name) {
+                    self.externs.push(name);
+                }
+            }
+            return Ok(());
+        }
+        if HEADER_DIRECTIVES.contains(&parts[0]) || LINK_DIRECTIVES.contains(&parts[0]) {
+            return Ok(());
+        }
+        if DATA_DIRECTIVES.contains(&parts[0]) {
+            self.sizes[self.section as usize] += self.data_len(line, self.sizes[self.section as usize])?;
+            return Ok(());
+        }
+        if self.section != SectionKind::Code {
+            return Err(LineError::new(parts[0], format!("instructions belong in .text, not .{}", self.section)));
+        }
 
-                if payload.len() > 255 {
-                   return Err(format!("Line {}: Payload too long", line_num));
+        let (op, wide) = parse_instruction(parts)?;
+        let operands = op.operands();
+        let payload_len = match operands.iter().position(|o| *o == Operand::Payload) {
+            Some(index) => {
+                expect_operands(parts, index)?;
+                payload(line, index)?.len()
+            }
+            None => {
+                expect_operands(parts, operands.len())?;
+                if let Some(extra) = parts.get(operands.len() + 1) {
+                    let warning = LineError::new(extra, format!("extra operand '{}' ignored", extra));
+                    self.report(line, Severity::Warning, warning);
                 }
+                0
+            }
+        };
+        self.sizes[self.section as usize] += op.encoded_len(wide, payload_len);
+        Ok(())
+    }
+
+    /// Pass 2: emit the line's bytes into its section
+    fn encode(&mut self, line: &SourceLine<'a>) -> LineResult<'a, ()> {
+        let parts = &line.parts;
+        if parts[0] == ".def" || line.trimmed.ends_with(':') {
+            return Ok(());
+        }
+        if let Some(kind) = section_directive(parts[0]) {
+            self.section = kind;
+            return Ok(());
+        }
+
+        let mut bytecode = Vec::new();
+        match parts[0] {
+            ".global" => {
+                expect_operands(parts, 1)?;
+                for name in &parts[1..] {
+                    if !self.labels.contains_key(name) {
+                        return Err(LineError::new(name, format!("'{}' is not a label", name)));
+                    }
+                    self.globals.insert(name);
+                }
+            }
+            ".extern" => {}
+            ".entry" if self.object => {
+                expect_operands(parts, 1)?;
+                match self.resolve(parts[1])? {
+                    Value { constant: 0, symbol: Some(Reloc { name, section: Some(_), .. }) } => self.entry = Some(name),
+                    _ => return Err(LineError::new(parts[1], ".entry in an object must name a label")),
+                }
+            }
+            ".entry" | ".stack" => {
+                expect_operands(parts, 1)?;
+                let value = u16::try_from(absolute(parts[1], self.resolve(parts[1])?)?).map_err(|_| {
+                    LineError::new(parts[1], format!("{} value '{}' does not fit in 16 bits", parts[0], parts[1]))
+                })?;
+                if parts[0] == ".entry" { self.image.entry = value } else { self.image.stack_size = value }
+            }
+            ".energy" => {
+                expect_operands(parts, 1)?;
+                self.image.energy = parts[1].parse::<f64>().ok()
+                    .filter(|energy| energy.is_finite() && *energy >= 0.0)
+                    .ok_or_else(|| LineError::new(parts[1], ".energy requires a finite, non-negative number"))?;
+            }
+            ".byte" | ".word" => {
+                let width = if parts[0] == ".byte" { 1 } else { 2 };
+                for arg in &parts[1..] {
+                    let value = self.resolve(arg)?;
+                    if self.relocate(arg, value, bytecode.len(), width)? {
+                        bytecode.resize(bytecode.len() + width, 0);
+                        continue;
+                    }
+                    let value = value.constant;
+                    // Like immediates, values may be written signed or unsigned
+                    let (min, max) = if width == 1 { (i8::MIN as i64, u8::MAX as i64) } else { (i16::MIN as i64, u16::MAX as i64) };
+                    if !(min..=max).contains(&value) {
+                        return Err(LineError::new(arg, format!("value '{}' ({}) does not fit in {} bits", arg, value, width * 8)));
+                    }
+                    bytecode.extend_from_slice(&(value as u16).to_le_bytes()[..width]);
+                }
+            }
+            ".ascii" => bytecode.extend_from_slice(&string_operand(line)?),
+            ".zero" | ".org" => {
+                let len = self.data_len(line, self.output[self.section as usize].len())?;
+                bytecode.resize(len, 0);
+            }
+            _ => bytecode = self.encode_instruction(line)?,
+        }
+        self.output[self.section as usize].extend_from_slice(&bytecode);
+        Ok(())
+    }
+
+    fn encode_instruction(&mut self, line: &SourceLine<'a>) -> LineResult<'a, Vec<u8>> {
+        let parts = &line.parts;
+        let (op, wide) = parse_instruction(parts)?;
+
+        let mut bytecode = Vec::new();
+        if wide {
+            bytecode.push(OpCode::Wide as u8);
+        }
+        bytecode.push(op as u8);
+
+        for (i, operand) in op.operands().iter().enumerate() {
+            if *operand == Operand::Payload {
+                let payload = payload(line, i)?;
                 bytecode.push(payload.len() as u8);
-                bytecode.extend_from_slice(payload);
-            },
-            "ENTANGLE" => {
-                bytecode.push(OpCode::Entangle as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "OBSERVE" => {
-                bytecode.push(OpCode::Observe as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-                bytecode.push(resolve_arg(parts[3], line_num)?);
-            },
-            "REVERT" => {
-                bytecode.push(OpCode::Revert as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "BRANCH" => {
-                bytecode.push(OpCode::Branch as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "MEMALLOC" => {
-                bytecode.push(OpCode::MemAlloc as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "MEMMAP" => {
-                bytecode.push(OpCode::MemMap as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-                bytecode.push(resolve_arg(parts[2], line_num)?);
-            },
-            "MEMSWAP" => {
-                bytecode.push(OpCode::MemSwap as u8);
-                bytecode.push(resolve_arg(parts[1], line_num)?);
-            },
-            "HALT" => bytecode.push(OpCode::Halt as u8),
-            _ => {}, // Should be caught in pass 1
-        }
-    }
-    
-    Ok(bytecode)
+                bytecode.extend_from_slice(&payload);
+                continue;
+            }
+
+            let arg = parts[i + 1];
+            if *operand == Operand::Reg {
+                let reg = parse_register(arg)
+                    .ok_or_else(|| LineError::new(arg, format!("invalid register '{}'", arg)))?;
+                bytecode.push(reg as u8);
+                continue;
+            }
+
+            let value = self.resolve(arg)?;
+            let width = match operand {
+                Operand::Addr | Operand::Word if wide => 2,
+                Operand::Imm16 => 2,
+                Operand::Imm32 => 4,
+                _ => 1,
+            };
+            if self.relocate(arg, value, bytecode.len(), width)? {
+                bytecode.resize(bytecode.len() + width, 0);
+                continue;
+            }
+            let value = value.constant;
+            match operand {
+                Operand::Addr | Operand::Word if wide => {
+                    if !(0..=u16::MAX as i64).contains(&value) {
+                        return Err(LineError::new(arg, format!("operand '{}' ({}) does not fit in 16 bits", arg, value)));
+                    }
+                    bytecode.extend_from_slice(&(value as u16).to_le_bytes());
+                }
+                Operand::Addr | Operand::Word => {
+                    if !(0..=u8::MAX as i64).contains(&value) {
+                        let name = arg.strip_prefix('@').unwrap_or(arg);
+                        let message = if self.addresses.contains_key(name) {
+                            format!("label '{}' is at address {}, beyond 255; use {}.W", name, value, parts[0].to_uppercase())
+                        } else {
+                            format!("operand '{}' ({}) needs wide addressing, use {}.W", arg, value, parts[0].to_uppercase())
+                        };
+                        return Err(LineError::new(arg, message));
+                    }
+                    bytecode.push(value as u8);
+                }
+                Operand::Byte => {
+                    if !(0..=u8::MAX as i64).contains(&value) {
+                        return Err(LineError::new(arg, format!("value '{}' ({}) does not fit in a byte", arg, value)));
+                    }
+                    bytecode.push(value as u8);
+                }
+                _ => {
+                    // Literals may be written signed or unsigned (two's complement)
+                    let (width, min, max) = if *operand == Operand::Imm16 {
+                        (2, i16::MIN as i64, u16::MAX as i64)
+                    } else {
+                        (4, i32::MIN as i64, u32::MAX as i64)
+                    };
+                    if !(min..=max).contains(&value) {
+                        return Err(LineError::new(arg, format!("value '{}' ({}) does not fit in {} bits", arg, value, width * 8)));
+                    }
+                    bytecode.extend_from_slice(&(value as u32).to_le_bytes()[..width]);
+                }
+            }
+        }
+        Ok(bytecode)
+    }
+
+    /// Evaluate an operand over numbers, definitions and labels
+    fn resolve(&self, arg: &'a str) -> LineResult<'a, Value<'a>> {
+        // `@addr` only selects the indirect form; the operand is the address
+        let expr = arg.strip_prefix('@').unwrap_or(arg);
+        evaluate(expr, &|name| self.lookup(name))
+    }
+
+    fn lookup(&self, name: &'a str) -> Result<Value<'a>, String> {
+        if let Some(value) = self.definitions.get(name) {
+            return Ok(Value::of(*value));
+        }
+        if let Some((section, offset, _)) = self.labels.get(name) {
+            if !self.object {
+                return Ok(Value::of(self.addresses[name] as i64));
+            }
+            let symbol = Reloc { name, section: Some(*section), offset: *offset as i64 };
+            return Ok(Value { constant: 0, symbol: Some(symbol) });
+        }
+        if self.externs.contains(&name) {
+            if !self.object {
+                return Err(format!("'{}' is external; assemble with assemble_object and link", name));
+            }
+            return Ok(Value { constant: 0, symbol: Some(Reloc { name, section: None, offset: 0 }) });
+        }
+        Err(format!("unknown symbol '{}'", name))
+    }
+
+    /// Evaluate an expression needed in pass 1, before labels have addresses
+    fn constant(&self, expr: &'a str) -> LineResult<'a, i64> {
+        let value = evaluate(expr, &|name| {
+            self.definitions.get(name).copied().map(Value::of)
+                .ok_or_else(|| format!("'{}' is not a constant defined above this line", name))
+        })?;
+        Ok(value.constant)
+    }
+
+    /// Record a relocation for a value `at` bytes into the line's output,
+    /// if the value depends on a symbol's address
+    fn relocate(&mut self, arg: &'a str, value: Value<'a>, at: usize, width: usize) -> LineResult<'a, bool> {
+        let Some(symbol) = value.symbol else { return Ok(false) };
+        let addend = i32::try_from(value.constant)
+            .map_err(|_| LineError::new(arg, format!("offset {} from '{}' is out of range", value.constant, symbol.name)))?;
+        let kind = match width {
+            1 => RelocationKind::Abs8,
+            2 => RelocationKind::Abs16,
+            _ => RelocationKind::Abs32,
+        };
+        self.relocations.push(Relocation {
+            section: self.section,
+            offset: (self.output[self.section as usize].len() + at) as u16,
+            kind,
+            symbol: symbol.name.to_string(),
+            addend,
+        });
+        Ok(true)
+    }
+
+    /// Bytes a data directive adds to a section currently `len` bytes long
+    fn data_len(&self, line: &SourceLine<'a>, len: usize) -> LineResult<'a, usize> {
+        let parts = &line.parts;
+        if self.section == SectionKind::Bss && !matches!(parts[0], ".zero" | ".org") {
+            return Err(LineError::new(parts[0], ".bss can only reserve space with .zero"));
+        }
+        match parts[0] {
+            ".byte" | ".word" => {
+                expect_operands(parts, 1)?;
+                let width = if parts[0] == ".byte" { 1 } else { 2 };
+                Ok(width * (parts.len() - 1))
+            }
+            ".ascii" => Ok(string_operand(line)?.len()),
+            // Sizes must be known in pass 1, so labels are not allowed
+            ".zero" => {
+                expect_operands(parts, 1)?;
+                usize::try_from(self.constant(parts[1])?)
+                    .map_err(|_| LineError::new(parts[1], ".zero requires a non-negative size"))
+            }
+            _ => {
+                expect_operands(parts, 1)?;
+                usize::try_from(self.constant(parts[1])?).ok()
+                    .and_then(|target| target.checked_sub(len))
+                    .ok_or_else(|| LineError::new(parts[1], format!(".org cannot move back from offset {}", len)))
+            }
+        }
+    }
+}
+
+/// Fail if a name is too long for the symbol tables of images and objects
+fn symbol_name(name: &str) -> LineResult<'_, ()> {
+    if name.len() > u8::MAX as usize {
+        return Err(LineError::new(name, format!("symbol name is {} bytes, at most {} fit", name.len(), u8::MAX)));
+    }
+    Ok(())
+}
+
+/// Fail unless a line has at least `count` operands after its first word
+fn expect_operands<'a>(parts: &[&'a str], count: usize) -> LineResult<'a, ()> {
+    let found = parts.len() - 1;
+    if found < count {
+        return Err(LineError::new(parts[0], format!("{} expects {} operand(s), found {}", parts[0], count, found)));
+    }
+    Ok(())
+}
+
+/// Value of an expression
+///
+/// In objects, label and external addresses are only known after linking,
+/// so a value may be a symbol's address plus a constant.
+#[derive(Debug, Clone, Copy)]
+struct Value<'a> {
+    constant: i64,
+    symbol: Option<Reloc<'a>>,
+}
+
+impl Value<'_> {
+    fn of(constant: i64) -> Self {
+        Self { constant, symbol: None }
+    }
+}
+
+/// A symbol whose address the linker fills in
+#[derive(Debug, Clone, Copy)]
+struct Reloc<'a> {
+    name: &'a str,
+    /// Section of a local label (None for externals)
+    section: Option<SectionKind>,
+    /// Offset of a local label within its section
+    offset: i64,
+}
+
+/// A value that must not depend on a relocatable address
+fn absolute<'a>(arg: &'a str, value: Value<'a>) -> LineResult<'a, i64> {
+    match value.symbol {
+        Some(symbol) => Err(LineError::new(arg, format!("'{}' is only known after linking", symbol.name))),
+        None => Ok(value.constant),
+    }
+}
+
+/// Evaluate an expression: `+ - * /`, parentheses, unary minus, decimal
+/// numbers and symbols looked up with `lookup`
+fn evaluate<'a>(expr: &'a str, lookup: &dyn Fn(&'a str) -> Result<Value<'a>, String>) -> LineResult<'a, Value<'a>> {
+    let mut parser = ExprParser { expr, pos: 0, lookup };
+    let value = parser.sum()?;
+    parser.skip_whitespace();
+    match parser.rest() {
+        "" => Ok(value),
+        rest => Err(LineError::new(rest, format!("unexpected '{}' in expression", rest))),
+    }
+}
+
+/// Recursive descent over one expression
+struct ExprParser<'a, 'f> {
+    expr: &'a str,
+    pos: usize,
+    lookup: &'f dyn Fn(&'a str) -> Result<Value<'a>, String>,
+}
+
+impl<'a> ExprParser<'a, '_> {
+    fn rest(&self) -> &'a str {
+        &self.expr[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.expr.len() - self.rest().trim_start().len();
+    }
+
+    /// Next character after whitespace, consumed if it is one of `ops`
+    fn operator(&mut self, ops: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        let c = self.rest().chars().next().filter(|c| ops.contains(c))?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn sum(&mut self) -> LineResult<'a, Value<'a>> {
+        let start = self.pos;
+        let mut value = self.product()?;
+        while let Some(op) = self.operator(&['+', '-']) {
+            let rhs = self.product()?;
+            let mut constant = if op == '+' { value.constant.checked_add(rhs.constant) } else { value.constant.checked_sub(rhs.constant) }
+                .ok_or_else(|| self.overflow(start))?;
+            value.symbol = match (op, value.symbol, rhs.symbol) {
+                (_, symbol, None) | ('+', None, symbol) => symbol,
+                // Two labels of one section stay the same distance apart
+                ('-', Some(a), Some(b)) if a.section.is_some() && a.section == b.section => {
+                    constant += a.offset - b.offset;
+                    None
+                }
+                _ => return Err(self.not_constant(start)),
+            };
+            value.constant = constant;
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> LineResult<'a, Value<'a>> {
+        let start = self.pos;
+        let mut value = self.unary()?;
+        while let Some(op) = self.operator(&['*', '/']) {
+            let rhs_start = self.pos;
+            let rhs = self.unary()?;
+            if value.symbol.is_some() || rhs.symbol.is_some() {
+                return Err(self.not_constant(start));
+            }
+            value.constant = match op {
+                '*' => value.constant.checked_mul(rhs.constant).ok_or_else(|| self.overflow(start))?,
+                _ if rhs.constant == 0 => return Err(LineError::new(&self.expr[rhs_start..self.pos], "division by zero")),
+                _ => value.constant / rhs.constant,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> LineResult<'a, Value<'a>> {
+        if self.operator(&['-']).is_some() {
+            let start = self.pos;
+            let value = self.unary()?;
+            if value.symbol.is_some() {
+                return Err(self.not_constant(start));
+            }
+            return value.constant.checked_neg().map(Value::of).ok_or_else(|| self.overflow(start));
+        }
+        if self.operator(&['(']).is_some() {
+            let value = self.sum()?;
+            if self.operator(&[')']).is_none() {
+                return Err(LineError::new(self.expr, "missing ')' in expression"));
+            }
+            return Ok(value);
+        }
+
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let token = &rest[..len];
+        self.pos += len;
+        if token.is_empty() {
+            let shown = rest.chars().next().map_or("end of expression".to_string(), |c| format!("'{}'", c));
+            return Err(LineError::new(if rest.is_empty() { self.expr } else { rest }, format!("expected a value, found {}", shown)));
+        }
+        if token.starts_with(|c: char| c.is_ascii_digit()) {
+            return token.parse::<i64>()
+                .map(Value::of)
+                .map_err(|_| LineError::new(token, format!("number '{}' is invalid or out of range", token)));
+        }
+        (self.lookup)(token).map_err(|message| LineError::new(token, message))
+    }
+
+    fn overflow(&self, start: usize) -> LineError<'a> {
+        LineError::new(&self.expr[start..self.pos], "expression overflows")
+    }
+
+    fn not_constant(&self, start: usize) -> LineError<'a> {
+        LineError::new(&self.expr[start..self.pos], "an address only known after linking can only be offset by a constant")
+    }
+}
+
+/// Bytes of the inline payload starting at operand `index`
+///
+/// Both passes size payloads with this, so they always agree. A payload is
+/// a string, a byte array, or unquoted text running to the end of the line.
+fn payload<'a>(line: &SourceLine<'a>, index: usize) -> LineResult<'a, Vec<u8>> {
+    let parts = &line.parts;
+    let Some(first) = parts.get(index + 1) else { return Ok(Vec::new()) };
+    let last = parts[parts.len() - 1];
+    let bytes = if first.starts_with(['"', '[']) {
+        if let Some(extra) = parts.get(index + 2) {
+            return Err(LineError::new(line.span(extra, last), format!("unexpected '{}' after payload", extra)));
+        }
+        literal(first)?
+    } else {
+        line.span(first, last).as_bytes().to_vec()
+    };
+    if bytes.len() > u8::MAX as usize {
+        let message = format!("payload is {} bytes, at most {} fit", bytes.len(), u8::MAX);
+        return Err(LineError::new(line.span(first, last), message));
+    }
+    Ok(bytes)
+}
+
+/// The quoted string operand of `.ascii`
+fn string_operand<'a>(line: &SourceLine<'a>) -> LineResult<'a, Vec<u8>> {
+    expect_operands(&line.parts, 1)?;
+    let text = line.parts[1];
+    if !text.starts_with('"') {
+        return Err(LineError::new(text, format!("{} expects a quoted string", line.parts[0])));
+    }
+    if let Some(extra) = line.parts.get(2) {
+        return Err(LineError::new(extra, format!("unexpected '{}' after string", extra)));
+    }
+    literal(text)
+}
+
+/// Bytes of a string or byte-array literal
+fn literal(word: &str) -> LineResult<'_, Vec<u8>> {
+    let (body, close) = if let Some(body) = word.strip_prefix('"') { (body, '"') } else { (&word[1..], ']') };
+    let mut bytes = Vec::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if c == close => {
+                let after = &body[i + 1..];
+                if !after.is_empty() {
+                    return Err(LineError::new(after, format!("unexpected '{}' after literal", after)));
+                }
+                if close == ']' {
+                    return byte_array(&body[..i]);
+                }
+                return Ok(bytes);
+            }
+            '\\' if close == '"' => {
+                let len = chars.next().map_or(0, |(_, c)| c.len_utf8());
+                let escape = &body[i..i + 1 + len];
+                let byte = match escape {
+                    "\\n" => b'\n',
+                    "\\t" => b'\t',
+                    "\\r" => b'\r',
+                    "\\0" => 0,
+                    "\\\\" | "\\\"" | "\\'" => escape.as_bytes()[1],
+                    "\\x" => {
+                        let hex = body.get(i + 2..i + 4).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+                        let Some(hex) = hex else {
+                            let end = body.len().min(i + 4);
+                            let span = body.get(i..end).unwrap_or(escape);
+                            return Err(LineError::new(span, "\\x expects two hex digits"));
+                        };
+                        chars.nth(1);
+                        u8::from_str_radix(hex, 16).unwrap_or_default()
+                    }
+                    _ => return Err(LineError::new(escape, format!("unknown escape '{}'", escape))),
+                };
+                bytes.push(byte);
+            }
+            _ => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    let what = if close == '"' { "string" } else { "byte array" };
+    Err(LineError::new(word, format!("unterminated {}", what)))
+}
+
+/// Comma-separated bytes, each decimal or `0x` hex
+fn byte_array(items: &str) -> LineResult<'_, Vec<u8>> {
+    if items.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    items.split(',').map(str::trim).map(|item| {
+        let value = match item.strip_prefix("0x").or_else(|| item.strip_prefix("0X")) {
+            Some(hex) => u8::from_str_radix(hex, 16),
+            None => item.parse::<u8>(),
+        };
+        // Reject the sign from_str_radix accepts
+        value.ok().filter(|_| !item.contains('+'))
+            .ok_or_else(|| LineError::new(item, format!("'{}' is not a byte (0-255 or 0x00-0xFF)", item)))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_narrow_encoding_unchanged() {
+        let bytecode = assemble("start:\nSET 10 5\nJUMP start").unwrap();
+        assert_eq!(bytecode, vec![0x01, 10, 5, 0x10, 0]);
+    }
+
+    #[test]
+    fn test_wide_encoding() {
+        let source = r#"
+            .def buffer 1024
+            SET.W buffer 7
+            JUMP.W end
+            end:
+            HALT
+        "#;
+        let bytecode = assemble(source).unwrap();
+        assert_eq!(bytecode, vec![0xFE, 0x01, 0x00, 0x04, 7, 0xFE, 0x10, 9, 0, 0xFF]);
+    }
+
+    #[test]
+    fn test_narrow_address_overflow_rejected() {
+        let err = assemble(".def buffer 1024\nSET buffer 7").unwrap_err();
+        assert!(err.to_string().contains("SET.W"));
+    }
+
+    #[test]
+    fn test_register_and_immediate_encoding() {
+        let source = "RSET r1 300\nRADDI.W r1 300\nRCMP r1 r2 40\nADDI 40 1";
+        assert!(assemble(source).is_err()); // 300 needs wide immediate
+        let bytecode = assemble("RSET.W r1 300\nRCMP r1 r2 40\nADDI 40 1").unwrap();
+        assert_eq!(bytecode, vec![0xFE, 0x30, 1, 0x2C, 0x01, 0x35, 1, 2, 40, 0x07, 40, 1]);
+        assert!(assemble("RSET r8 1").unwrap_err().to_string().contains("invalid register"));
+    }
+
+    #[test]
+    fn test_width_suffixed_encoding() {
+        let bytecode = assemble(".def big 100000\nSET32 40 big\nSET16 44 -2\nCMPS16 44 46 48").unwrap();
+        assert_eq!(bytecode, vec![
+            0x50, 40, 0xA0, 0x86, 0x01, 0x00,
+            0x40, 44, 0xFE, 0xFF,
+            0x46, 44, 46, 48,
+        ]);
+        assert!(assemble("SET16 40 70000").is_err());
+    }
+
+    #[test]
+    fn test_sections_and_header() {
+        let source = r#"
+            .entry main
+            .stack 16
+            .energy 25
+            .bss
+            counter:
+                .zero 2
+            .rodata
+            greeting:
+                .byte 104 105
+            .text
+            main:
+                SET counter 1
+                HALT
+        "#;
+        let image = assemble_image(source).unwrap();
+        assert_eq!((image.entry, image.stack_size, image.energy), (0, 16, 25.0));
+        assert_eq!(image.section(SectionKind::Code).unwrap().data, vec![0x01, 6, 1, 0xFF]);
+        assert_eq!(image.section(SectionKind::Rodata).unwrap().addr, 4);
+        assert_eq!(image.section(SectionKind::Bss).unwrap().data, vec![0, 0]);
+        assert_eq!(image.symbol("greeting"), Some(4));
+        assert_eq!(image.symbol("counter"), Some(6));
+
+        // Images serialize, plain programs stay raw bytecode
+        assert_eq!(ProgramImage::from_bytes(&assemble(source).unwrap()).unwrap(), image);
+        assert!(assemble(".data\nSET 10 5").unwrap_err().to_string().contains(".text"));
+        assert!(assemble(".bss\n.byte 1").unwrap_err().to_string().contains(".zero"));
+        assert!(assemble(".energy inf\nHALT").unwrap_err().to_string().contains("finite"));
+    }
+
+    #[test]
+    fn test_indirect_operand_selects_form() {
+        let bytecode = assemble("BRANCH 100 102\nSIGNAL @102 \"go\"").unwrap();
+        assert_eq!(bytecode, vec![0xF4, 100, 102, 0xF6, 102, 2, b'g', b'o']);
+        assert!(assemble("SET @10 5").unwrap_err().to_string().contains("indirect"));
+    }
+
+    #[test]
+    fn test_all_errors_reported_with_positions() {
+        let source = "start:\n  STE 10 5\n  SET 10\n  SETI 300\nstart:\n  .zero 300\nfar:\n  JUMP far\n  SET 10 5 6";
+        let err = assemble_image_named("boot.pasm", source).unwrap_err();
+        let found: Vec<_> = err.diagnostics.iter()
+            .map(|d| (d.line, d.column, d.severity, d.message.as_str()))
+            .collect();
+        assert_eq!(found, vec![
+            (2, 3, Severity::Error, "unknown opcode 'STE'"),
+            (3, 3, Severity::Error, "SET expects 2 operand(s), found 1"),
+            (4, 3, Severity::Error, "unknown opcode 'SETI'"),
+            (5, 1, Severity::Error, "label 'start' is already defined on line 1"),
+            (8, 8, Severity::Error, "label 'far' is at address 300, beyond 255; use JUMP.W"),
+            (9, 12, Severity::Warning, "extra operand '6' ignored"),
+        ]);
+        assert_eq!(err.errors().count(), 5);
+        assert!(err.to_string().contains("boot.pasm:8:8"));
+
+        // Immediates that do not fit are rejected rather than truncated
+        let err = assemble("ADDI 10 256\nRSET r1 99999999999999999999").unwrap_err();
+        assert_eq!(err.diagnostics[0].message, "value '256' (256) does not fit in a byte");
+        assert_eq!((err.diagnostics[1].line, err.diagnostics[1].column), (2, 9));
+
+        // Warnings alone do not fail assembly, trailing comments are not operands
+        assert_eq!(assemble("SET 10 5 6").unwrap(), vec![0x01, 10, 5]);
+        assert!(check("boot.pasm", "SET 10 5 # five").is_empty());
+    }
+
+    #[test]
+    fn test_expressions_and_data_directives() {
+        let source = r#"
+            .def queue_start 10
+            .def queue_end queue_start + 2 * 5
+            SET queue_start + 5 (queue_end - queue_start) / 2
+            JUMP data + 1
+            .org 8
+            data:
+            .ascii "hi" # greeting
+            .byte -1 data-1
+        "#;
+        let bytecode = assemble(source).unwrap();
+        assert_eq!(bytecode, vec![0x01, 15, 5, 0x10, 9, 0, 0, 0, b'h', b'i', 0xFF, 7]);
+
+        // A signed literal after whitespace is its own operand
+        assert_eq!(assemble("SET16 44 -2").unwrap(), vec![0x40, 44, 0xFE, 0xFF]);
+
+        let err = assemble(".zero later\nSET 1 2 / 0\n.org 0\n.ascii hi\nlater:").unwrap_err();
+        let messages: Vec<&str> = err.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec![
+            "'later' is not a constant defined above this line",
+            "division by zero",
+            ".org cannot move back from offset 3",
+            ".ascii expects a quoted string",
+        ]);
+    }
+
+    #[test]
+    fn test_service_sources_assemble() {
+        let services = Path::new(env!("CARGO_MANIFEST_DIR")).join("../services");
+        let mut count = 0;
+        for entry in std::fs::read_dir(services).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "asm") {
+                let code = assemble_file(&path).unwrap_or_else(|e| panic!("{}", e));
+                assert!(crate::universe::verifier::verify(&code).is_valid(), "{}", path.display());
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+
+        // Includes are resolved next to the including file
+        let err = assemble_image_named("/nonexistent/boot.pasm", ".include \"prelude.inc\"").unwrap_err();
+        assert!(err.diagnostics[0].message.contains("/nonexistent/prelude.inc"));
+    }
+
+    #[test]
+    fn test_object_relocations() {
+        let source = ".extern memcpy\n.global main\n.entry main\nmain:\n  CALL.W memcpy\n  SET buffer + 1 7\n  SET 10 end - main\nend:\n  HALT\n.data\nbuffer:\n  .zero 4";
+        let object = assemble_object("main.pasm", source).unwrap();
+        assert_eq!(object.entry.as_deref(), Some("main"));
+        assert_eq!(object.imports, vec!["memcpy".to_string()]);
+        assert_eq!(object.section(SectionKind::Code).unwrap().data, vec![0xFE, 0x20, 0, 0, 0x01, 0, 7, 0x01, 10, 10, 0xFF]);
+        let relocations: Vec<_> = object.relocations.iter()
+            .map(|r| (r.section, r.offset, r.kind, r.symbol.as_str(), r.addend))
+            .collect();
+        assert_eq!(relocations, vec![
+            (SectionKind::Code, 2, RelocationKind::Abs16, "memcpy", 0),
+            (SectionKind::Code, 5, RelocationKind::Abs8, "buffer", 1),
+        ]);
+        let exported: Vec<_> = object.symbols.iter().filter(|s| s.global).map(|s| s.name.as_str()).collect();
+        assert_eq!(exported, vec!["main"]);
+
+        let error = |source: &str| assemble_object("bad.pasm", source).unwrap_err().to_string();
+        assert!(error(".extern f\nSET f * 2 1").contains("can only be offset by a constant"));
+        assert!(error(".global nowhere\nHALT").contains("'nowhere' is not a label"));
+        assert!(error(".entry 3\nHALT").contains(".entry in an object must name a label"));
+        assert!(error(&format!(".global {0}\n{0}:\nHALT", "l".repeat(300))).contains("symbol name is 300 bytes, at most 255 fit"));
+        assert!(assemble(".extern f\nCALL f").unwrap_err().to_string().contains("'f' is external"));
+    }
+
+    #[test]
+    fn test_payload_literals() {
+        let source = "SIGNAL 1 \"a  b\\n\\x00\\\"#\" # note\nend:\nSIGNAL 1 [0x01, 0xFF, 7]\nSIGNAL 1 \"é\"";
+        let image = assemble_image(source).unwrap();
+        let code = &image.section(SectionKind::Code).unwrap().data;
+        assert_eq!(&code[2..11], b"\x08a  b\n\x00\"#");
+        assert_eq!(image.symbol("end"), Some(11)); // Labels after a payload land where pass 2 puts them
+        assert_eq!(&code[13..17], &[3, 0x01, 0xFF, 7]);
+        assert_eq!(&code[19..], &[2, 0xC3, 0xA9]);
+
+        let error = |source: &str| assemble(source).unwrap_err().diagnostics[0].message.clone();
+        assert_eq!(error("SIGNAL 1 \"\\q\""), "unknown escape '\\q'");
+        assert_eq!(error("SIGNAL 1 \"\\x4\""), "\\x expects two hex digits");
+        assert_eq!(error("SIGNAL 1 \"open # no comment"), "unterminated string");
+        assert_eq!(error("SIGNAL 1 [1, 0x100]"), "'0x100' is not a byte (0-255 or 0x00-0xFF)");
+        assert_eq!(error("SIGNAL 1 \"a\" b"), "unexpected 'b' after payload");
+        assert_eq!(error(&format!("SIGNAL 1 \"{}\"", "\\x00".repeat(256))), "payload is 256 bytes, at most 255 fit");
+    }
 }
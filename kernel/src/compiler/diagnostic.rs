@@ -0,0 +1,168 @@
+//! Assembler diagnostics
+//!
+//! The assembler reports every problem it finds in one run, each with its
+//! position and the offending source line:
+//!
+//! ```text
+//! error: unknown opcode 'STE'
+//!  --> boot.pasm:3:5
+//!   |
+//! 3 |     STE 10 5
+//!   |     ^^^
+//! ```
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Suspicious but assembled anyway
+    Warning,
+    /// Assembly fails
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found at a source position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmDiagnostic {
+    /// Source file name
+    pub file: String,
+    /// One-based line number (0 when the problem concerns the whole file)
+    pub line: usize,
+    /// One-based column of the first offending character
+    pub column: usize,
+    /// Width of the offending span in characters
+    pub width: usize,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// What is wrong
+    pub message: String,
+    /// The offending source line
+    pub source_line: String,
+}
+
+impl AsmDiagnostic {
+    /// Diagnostic pointing at `span`, which must be a slice of `source_line`
+    ///
+    /// Spans from elsewhere point at the start of the line.
+    pub fn at(severity: Severity, file: &str, line: usize, source_line: &str, span: &str, message: impl Into<String>) -> Self {
+        let (start, width) = match span_offset(source_line, span) {
+            Some(start) => (start, span.chars().count().max(1)),
+            None => (0, 1),
+        };
+        Self {
+            file: file.to_string(),
+            line,
+            column: source_line[..start].chars().count() + 1,
+            width,
+            severity,
+            message: message.into(),
+            source_line: source_line.to_string(),
+        }
+    }
+
+    /// Diagnostic about a whole file rather than one of its lines
+    pub fn file_level(severity: Severity, file: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            line: 0,
+            column: 0,
+            width: 0,
+            severity,
+            message: message.into(),
+            source_line: String::new(),
+        }
+    }
+
+    /// Multi-line rendering with the source snippet and a marker under the span
+    pub fn render(&self) -> String {
+        if self.line == 0 {
+            return format!("{}: {}\n --> {}\n", self.severity, self.message, self.file);
+        }
+        let gutter = self.line.to_string().len();
+        // Keep tabs so the marker lines up with the snippet
+        let indent: String = self.source_line.chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "{severity}: {message}\n{pad}--> {file}:{line}:{column}\n{pad} |\n{line} | {source}\n{pad} | {indent}{marker}\n",
+            severity = self.severity,
+            message = self.message,
+            pad = " ".repeat(gutter),
+            file = self.file,
+            line = self.line,
+            column = self.column,
+            source = self.source_line,
+            marker = "^".repeat(self.width),
+        )
+    }
+}
+
+impl std::fmt::Display for AsmDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}: {}", self.file, self.severity, self.message);
+        }
+        write!(f, "{}:{}:{}: {}: {}", self.file, self.line, self.column, self.severity, self.message)
+    }
+}
+
+/// Failed assembly, with every diagnostic found in source order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Errors and warnings, sorted by position
+    pub diagnostics: Vec<AsmDiagnostic>,
+}
+
+impl AsmError {
+    /// Diagnostics that made assembly fail
+    pub fn errors(&self) -> impl Iterator<Item = &AsmDiagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic.render())?;
+        }
+        write!(f, "{} error(s)", self.errors().count())
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Byte offset of `span` within `line`, if it is a slice of it
+pub(crate) fn span_offset(line: &str, span: &str) -> Option<usize> {
+    let start = (span.as_ptr() as usize).checked_sub(line.as_ptr() as usize)?;
+    (start + span.len() <= line.len()).then_some(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_points_at_span() {
+        let line = "    STE 10 5";
+        let diagnostic = AsmDiagnostic::at(Severity::Error, "boot.pasm", 3, line, &line[4..7], "unknown opcode 'STE'");
+        assert_eq!((diagnostic.column, diagnostic.width), (5, 3));
+        assert_eq!(diagnostic.to_string(), "boot.pasm:3:5: error: unknown opcode 'STE'");
+        assert_eq!(
+            diagnostic.render(),
+            "error: unknown opcode 'STE'\n --> boot.pasm:3:5\n  |\n3 |     STE 10 5\n  |     ^^^\n"
+        );
+
+        // A span from another string falls back to the start of the line
+        let elsewhere = AsmDiagnostic::at(Severity::Warning, "boot.pasm", 3, line, "STE", "odd");
+        assert_eq!((elsewhere.column, elsewhere.width), (1, 1));
+    }
+}
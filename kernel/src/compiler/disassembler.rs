@@ -0,0 +1,156 @@
+//! Disassembler for Universal ISA bytecode
+//!
+//! Decodes with the processor's own `Instruction::decode`, so instruction
+//! lengths always agree with `OpCode::from_u8` and `UniversalProcessor::step`.
+//! Listings print one instruction per line:
+//!
+//! ```text
+//! 0003  FE 10 00 01              JUMP.W 256                   ; 0.0006 J
+//! ```
+//!
+//! The mnemonic and operands column is valid assembler source.
+
+use crate::universe::isa::{Instruction, Operand, ProcessorFault};
+use crate::universe::EnergyCostModel;
+
+/// An instruction (or undecodable byte) at a program address
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedInstruction {
+    /// Address of the first byte
+    pub addr: usize,
+    /// Raw encoding, including any WIDE prefix
+    pub bytes: Vec<u8>,
+    /// Decoded instruction, or the fault the processor raises here
+    pub decoded: Result<Instruction, ProcessorFault>,
+    /// Mnemonic with `.W` suffix if wide (`.byte` for undecodable bytes)
+    pub mnemonic: String,
+    /// Operands as assembler source (`r3`, `@102`, `"text"`...)
+    pub operands: Vec<String>,
+    /// Energy of one execution, excluding erasure and data-dependent costs
+    pub cost: f64,
+}
+
+impl DecodedInstruction {
+    /// Mnemonic and operands as one line of assembler source
+    pub fn text(&self) -> String {
+        std::iter::once(self.mnemonic.as_str())
+            .chain(self.operands.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl std::fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        write!(f, "{:04X}  {:<24} {:<28} ; ", self.addr, bytes.join(" "), self.text())?;
+        match &self.decoded {
+            Ok(_) => write!(f, "{:.4} J", self.cost),
+            Err(fault) => write!(f, "{}", fault),
+        }
+    }
+}
+
+/// Disassemble code loaded at address 0, priced with the default cost model
+pub fn disassemble(code: &[u8]) -> Vec<DecodedInstruction> {
+    disassemble_with(code, 0, &EnergyCostModel::default())
+}
+
+/// Disassemble code loaded at `base`, priced with `costs`
+///
+/// Bytes that do not decode are listed one at a time as `.byte`, and
+/// decoding resumes at the next byte.
+pub fn disassemble_with(code: &[u8], base: usize, costs: &EnergyCostModel) -> Vec<DecodedInstruction> {
+    let mut listing = Vec::new();
+    let mut ip = 0;
+    while ip < code.len() {
+        let entry = match Instruction::decode(code, ip) {
+            Ok(insn) => {
+                let payload = insn.payload.clone().map(|range| &code[range]);
+                let mut mnemonic = insn.opcode.mnemonic().to_string();
+                if insn.wide {
+                    mnemonic.push_str(".W");
+                }
+                let payload_len = payload.map_or(0, <[u8]>::len);
+                DecodedInstruction {
+                    addr: base + ip,
+                    bytes: code[ip..ip + insn.len].to_vec(),
+                    mnemonic,
+                    operands: format_operands(&insn, payload),
+                    cost: costs.cycle + costs.op(insn.opcode).charge(payload_len),
+                    decoded: Ok(insn),
+                }
+            }
+            Err(fault) => DecodedInstruction {
+                addr: base + ip,
+                bytes: vec![code[ip]],
+                decoded: Err(fault),
+                mnemonic: ".byte".to_string(),
+                operands: vec![code[ip].to_string()],
+                cost: 0.0,
+            },
+        };
+        ip += entry.bytes.len();
+        listing.push(entry);
+    }
+    listing
+}
+
+/// Render a program as a text listing, one instruction per line
+pub fn listing(code: &[u8]) -> String {
+    disassemble(code).iter().map(|insn| format!("{}\n", insn)).collect()
+}
+
+/// Operands of a decoded instruction as assembler source
+fn format_operands(insn: &Instruction, payload: Option<&[u8]>) -> Vec<String> {
+    insn.opcode.operands().iter().enumerate().map(|(i, operand)| {
+        let value = insn.args[i];
+        match operand {
+            Operand::Reg => format!("r{}", value),
+            Operand::Addr if i == 0 && insn.opcode.is_indirect() => format!("@{}", value),
+            Operand::Payload => format!("\"{}\"", payload.unwrap_or_default().escape_ascii()),
+            _ => value.to_string(),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::assemble;
+    use crate::universe::isa::OpCode;
+
+    #[test]
+    fn test_listing_reassembles() {
+        let source = "SET 10 5\nJUMP.W 300\nRCMP r1 r2 40\nSIGNAL @102 \"go\\t\\x00\\\"\"\nSET32 40 100000\nHALT";
+        let code = assemble(source).unwrap();
+        let listing = disassemble(&code);
+
+        let text: Vec<String> = listing.iter().map(DecodedInstruction::text).collect();
+        assert_eq!(text.join("\n"), source);
+        assert_eq!(listing[1].addr, 3);
+        assert_eq!(listing[1].bytes, vec![0xFE, 0x10, 0x2C, 0x01]);
+        assert!(listing[3].cost > listing[0].cost); // Signals cost more than SET
+    }
+
+    #[test]
+    fn test_lengths_match_the_processor_table() {
+        for op in (0..=u8::MAX).filter_map(OpCode::from_u8).filter(|op| *op != OpCode::Wide) {
+            let mut code = vec![op as u8];
+            code.resize(8, 0); // Register 0, empty payload
+            let first = &disassemble(&code)[0];
+            assert_eq!(first.bytes.len(), op.encoded_len(false, 0), "{:?}", op);
+            assert_eq!(first.mnemonic, op.mnemonic());
+        }
+    }
+
+    #[test]
+    fn test_undecodable_bytes() {
+        let listing = disassemble(&[0xEE, 0xFF, 0x30, 9]);
+        assert_eq!(listing[0].decoded, Err(ProcessorFault::InvalidOpcode));
+        assert_eq!(listing[0].text(), ".byte 238");
+        assert_eq!(listing[1].text(), "HALT");
+        assert_eq!(listing[2].decoded, Err(ProcessorFault::InvalidRegister));
+        assert!(listing[0].to_string().ends_with("invalid opcode"));
+    }
+}
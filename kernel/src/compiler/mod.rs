@@ -1,3 +1,10 @@
 pub mod assembler;
+pub mod diagnostic;
+pub mod disassembler;
+mod preprocessor;
+pub mod runtime;
 
-pub use assembler::assemble;
+pub use assembler::{assemble, assemble_file, assemble_image, assemble_image_named, assemble_object, check};
+pub use diagnostic::{AsmDiagnostic, AsmError, Severity};
+pub use disassembler::{disassemble, DecodedInstruction};
+pub use runtime::{link_with_runtime, runtime_library};
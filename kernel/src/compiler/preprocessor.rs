@@ -0,0 +1,256 @@
+//! Assembler preprocessor: includes and macros
+//!
+//! Runs before the assembler's two passes and flattens a source file into
+//! the lines they see:
+//!
+//! - `.include "file"` splices in another file, resolved relative to the
+//!   including file
+//! - `.macro name param...` ... `.endm` defines a macro, invoked as
+//!   `name arg...`. Arguments are single words. In the body, every
+//!   identifier naming a parameter is replaced by its argument, and `\@`
+//!   by a number unique to the expansion (for local labels: `loop\@:`).
+
+use super::assembler::is_mnemonic;
+use super::diagnostic::{AsmDiagnostic, Severity};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Deepest nesting of includes and macro expansions
+const MAX_DEPTH: usize = 16;
+
+/// A source line ready for assembly
+pub(crate) struct Line {
+    /// File the line comes from
+    pub file: String,
+    /// One-based line number in that file
+    pub number: usize,
+    /// Text after macro substitution
+    pub text: String,
+    /// Macro the line was expanded from
+    pub expanded_from: Option<String>,
+}
+
+struct Macro {
+    params: Vec<String>,
+    /// Body lines with their line numbers
+    body: Vec<(usize, String)>,
+    file: String,
+}
+
+/// Output of preprocessing
+pub(crate) struct Preprocessed {
+    pub lines: Vec<Line>,
+    /// Problems found, each with the number of lines emitted before it
+    pub diagnostics: Vec<(usize, AsmDiagnostic)>,
+}
+
+/// Flatten `source`, read from `file`, into assembler lines
+pub(crate) fn preprocess(file: &str, source: &str) -> Preprocessed {
+    let mut preprocessor = Preprocessor {
+        macros: HashMap::new(),
+        expansions: 0,
+        includes: vec![canonical(Path::new(file))],
+        out: Preprocessed { lines: Vec::new(), diagnostics: Vec::new() },
+    };
+    let lines = source.lines().enumerate().map(|(index, text)| (index + 1, text.to_string())).collect();
+    preprocessor.lines(file, lines, 0, None);
+    preprocessor.out
+}
+
+struct Preprocessor {
+    macros: HashMap<String, Macro>,
+    expansions: usize,
+    /// Files being included, innermost last
+    includes: Vec<PathBuf>,
+    out: Preprocessed,
+}
+
+impl Preprocessor {
+    fn lines(&mut self, file: &str, lines: Vec<(usize, String)>, depth: usize, expanded_from: Option<&str>) {
+        let mut lines = lines.into_iter();
+        while let Some((number, text)) = lines.next() {
+            let mut words = text.split_whitespace();
+            match words.next() {
+                Some(".include") => self.include(file, number, &text, depth),
+                Some(".macro") => {
+                    let params: Vec<&str> = words.take_while(|w| !is_comment(w)).collect();
+                    let mut body = Vec::new();
+                    let mut closed = false;
+                    for (number, text) in lines.by_ref() {
+                        if text.split_whitespace().next() == Some(".endm") {
+                            closed = true;
+                            break;
+                        }
+                        body.push((number, text));
+                    }
+                    self.define(file, number, &text, &params, body, closed);
+                }
+                Some(".endm") => self.error(file, number, &text, text.trim(), ".endm without .macro"),
+                Some(name) if self.macros.contains_key(name) => self.expand(file, number, &text, depth),
+                _ => self.out.lines.push(Line {
+                    file: file.to_string(),
+                    number,
+                    text,
+                    expanded_from: expanded_from.map(str::to_string),
+                }),
+            }
+        }
+    }
+
+    fn define(&mut self, file: &str, number: usize, text: &str, words: &[&str], body: Vec<(usize, String)>, closed: bool) {
+        let Some((name, params)) = words.split_first() else {
+            return self.error(file, number, text, text.trim(), ".macro requires a name");
+        };
+        if !closed {
+            self.error(file, number, text, name, format!("macro '{}' has no .endm", name));
+        } else if is_mnemonic(name) || name.starts_with('.') {
+            self.error(file, number, text, name, format!("macro '{}' would shadow an opcode or directive", name));
+        } else if self.macros.contains_key(*name) {
+            self.error(file, number, text, name, format!("macro '{}' is already defined", name));
+        } else {
+            let params = params.iter().map(|p| p.to_string()).collect();
+            self.macros.insert(name.to_string(), Macro { params, body, file: file.to_string() });
+        }
+    }
+
+    fn expand(&mut self, file: &str, number: usize, text: &str, depth: usize) {
+        let words: Vec<&str> = text.split_whitespace().take_while(|w| !is_comment(w)).collect();
+        let (name, args) = (words[0], &words[1..]);
+        if depth >= MAX_DEPTH {
+            return self.error(file, number, text, name, format!("macro '{}' nests too deeply", name));
+        }
+        let mac = &self.macros[name];
+        if args.len() != mac.params.len() {
+            let message = format!("macro '{}' expects {} argument(s), found {}", name, mac.params.len(), args.len());
+            return self.error(file, number, text, name, message);
+        }
+
+        self.expansions += 1;
+        let bindings: HashMap<&str, &str> = mac.params.iter().map(String::as_str).zip(args.iter().copied()).collect();
+        let body = mac.body.iter()
+            .map(|(number, line)| (*number, substitute(line, &bindings, self.expansions)))
+            .collect();
+        let macro_file = mac.file.clone();
+        self.lines(&macro_file, body, depth + 1, Some(name));
+    }
+
+    fn include(&mut self, file: &str, number: usize, text: &str, depth: usize) {
+        let arg = text.trim()[".include".len()..].trim();
+        let Some(name) = arg.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) else {
+            return self.error(file, number, text, text.trim(), ".include expects a quoted file name");
+        };
+        let path = Path::new(file).parent().unwrap_or(Path::new("")).join(name);
+        if self.includes.contains(&canonical(&path)) {
+            return self.error(file, number, text, arg, format!("'{}' includes itself", name));
+        }
+        if depth >= MAX_DEPTH {
+            return self.error(file, number, text, arg, format!("including '{}' nests more than {} levels deep", name, MAX_DEPTH));
+        }
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => return self.error(file, number, text, arg, format!("cannot read '{}': {}", path.display(), e)),
+        };
+
+        self.includes.push(canonical(&path));
+        let lines = source.lines().enumerate().map(|(index, text)| (index + 1, text.to_string())).collect();
+        self.lines(&path.display().to_string(), lines, depth + 1, None);
+        self.includes.pop();
+    }
+
+    fn error(&mut self, file: &str, number: usize, text: &str, span: &str, message: impl Into<String>) {
+        let diagnostic = AsmDiagnostic::at(Severity::Error, file, number, text, span, message);
+        self.out.diagnostics.push((self.out.lines.len(), diagnostic));
+    }
+}
+
+/// Check if a word starts a trailing comment
+pub(crate) fn is_comment(word: &str) -> bool {
+    word.starts_with('#') || word.starts_with("//")
+}
+
+/// Path used to recognize a file included twice on the same chain
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Substitute macro parameters and `\@` in a body line, leaving strings alone
+fn substitute(line: &str, bindings: &HashMap<&str, &str>, unique: usize) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    let mut in_string = false;
+    while let Some(c) = rest.chars().next() {
+        if in_string && c == '\\' {
+            // Copy the escape whole so `\"` does not end the string
+            let len = rest[1..].chars().next().map_or(0, char::len_utf8);
+            out.push_str(&rest[..1 + len]);
+            rest = &rest[1 + len..];
+            continue;
+        }
+        if c == '"' {
+            in_string = !in_string;
+        } else if !in_string && rest.starts_with("\\@") {
+            out.push_str(&unique.to_string());
+            rest = &rest[2..];
+            continue;
+        } else if !in_string && (c.is_ascii_alphabetic() || c == '_') {
+            let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let ident = &rest[..len];
+            out.push_str(bindings.get(ident).copied().unwrap_or(ident));
+            rest = &rest[len..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_expansion() {
+        let source = ".macro countdown counter target\nloop\\@:\n  SUBI counter 1\n  JUMPIF counter target\n.endm\ncountdown temp loop\ncountdown temp done";
+        let out = preprocess("demo.pasm", source);
+        let texts: Vec<&str> = out.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec![
+            "loop1:", "  SUBI temp 1", "  JUMPIF temp loop",
+            "loop2:", "  SUBI temp 1", "  JUMPIF temp done",
+        ]);
+        assert_eq!(out.lines[1].number, 3);
+        assert_eq!(out.lines[1].expanded_from.as_deref(), Some("countdown"));
+        assert!(out.diagnostics.is_empty());
+
+        // Strings are not substituted
+        let bindings = HashMap::from([("name", "x")]);
+        assert_eq!(substitute("SIGNAL name \"name\"", &bindings, 1), "SIGNAL x \"name\"");
+        assert_eq!(substitute("SIGNAL 1 \"a\\\" name\" name", &bindings, 1), "SIGNAL 1 \"a\\\" name\" x");
+    }
+
+    #[test]
+    fn test_preprocessor_errors() {
+        let source = ".macro SET a\n.endm\n.macro twice a\n  a\n.endm\ntwice\n.include \"missing.inc\"\n.macro open";
+        let messages: Vec<String> = preprocess("demo.pasm", source).diagnostics.into_iter()
+            .map(|(_, d)| format!("{}: {}", d.line, d.message))
+            .collect();
+        assert_eq!(messages[0], "1: macro 'SET' would shadow an opcode or directive");
+        assert_eq!(messages[1], "6: macro 'twice' expects 1 argument(s), found 0");
+        assert!(messages[2].starts_with("7: cannot read 'missing.inc'"));
+        assert_eq!(messages[3], "8: macro 'open' has no .endm");
+    }
+
+    #[test]
+    fn test_include_depth_limit() {
+        let dir = std::env::temp_dir().join(format!("paradox-includes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for level in 0..=MAX_DEPTH {
+            std::fs::write(dir.join(format!("{}.inc", level)), format!(".include \"{}.inc\"", level + 1)).unwrap();
+        }
+        let root = dir.join("root.pasm").display().to_string();
+        let out = preprocess(&root, ".include \"0.inc\"");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(out.diagnostics.len(), 1);
+        assert_eq!(out.diagnostics[0].1.message, "including '16.inc' nests more than 16 levels deep");
+    }
+}
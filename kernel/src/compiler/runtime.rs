@@ -0,0 +1,53 @@
+//! Runtime library
+//!
+//! Routines shared by linked programs, assembled from the sources in
+//! `runtime/`. `link_with_runtime` pulls in only the routines a program
+//! calls. Routines take their arguments in registers and must be called
+//! with `CALL.W`:
+//!
+//! | Routine     | Arguments                     | Results                                   |
+//! |-------------|-------------------------------|-------------------------------------------|
+//! | `memcpy`    | r0 dest, r1 source, r2 length | (clobbers r3)                             |
+//! | `checksum`  | r0 address, r1 length         | r2 byte sum (clobbers r3)                 |
+//! | `msg_parse` | r0 RECV buffer                | r1 source, r2 type, r3 length, r0 payload |
+
+use super::assembler::assemble_object;
+use paradoxef::{link, LinkError, ObjectFile, ProgramImage};
+
+/// Library sources with the file names used in diagnostics
+const SOURCES: [(&str, &str); 3] = [
+    ("runtime/memcpy.asm", include_str!("runtime/memcpy.asm")),
+    ("runtime/checksum.asm", include_str!("runtime/checksum.asm")),
+    ("runtime/msg_parse.asm", include_str!("runtime/msg_parse.asm")),
+];
+
+/// Assemble the runtime library, one object per routine
+pub fn runtime_library() -> Vec<ObjectFile> {
+    SOURCES.iter()
+        .map(|(file, source)| assemble_object(file, source).unwrap_or_else(|e| panic!("runtime library: {}", e)))
+        .collect()
+}
+
+/// Link objects against the runtime library
+pub fn link_with_runtime(objects: &[ObjectFile]) -> Result<ProgramImage, LinkError> {
+    link(objects, &runtime_library())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_called_routines_are_linked() {
+        let library = runtime_library();
+        for (object, name) in library.iter().zip(["memcpy", "checksum", "msg_parse"]) {
+            assert!(object.symbol(name).is_some_and(|s| s.global), "{}", name);
+        }
+
+        let main = assemble_object("main.pasm", ".extern checksum\nCALL.W checksum\nHALT").unwrap();
+        let image = link_with_runtime(&[main]).unwrap();
+        assert!(image.symbol("checksum").is_some());
+        assert!(image.symbol("memcpy").is_none());
+        assert!(matches!(link_with_runtime(&[]), Ok(image) if image.symbols.is_empty()));
+    }
+}
@@ -0,0 +1,22 @@
+# checksum: sum the r1 bytes at address r0 into r2
+# On return r0 points past the bytes and r1 is 0; clobbers r3
+
+.global checksum
+
+.text
+checksum:
+    RSET r2 0
+checksum_loop:
+    RCMPI.W r1 0 checksum_more
+    JUMPIF.W checksum_more checksum_byte
+    RET.W
+checksum_byte:
+    LOAD r3 r0 0
+    RADD r2 r3
+    RADDI r0 1
+    RSUBI r1 1
+    JUMP.W checksum_loop
+
+.bss
+checksum_more:
+    .zero 1
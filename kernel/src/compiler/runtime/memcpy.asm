@@ -0,0 +1,21 @@
+# memcpy: copy r2 bytes from address r1 to address r0
+# On return r0 and r1 point past the copied bytes and r2 is 0; clobbers r3
+
+.global memcpy
+
+.text
+memcpy:
+    RCMPI.W r2 0 memcpy_more
+    JUMPIF.W memcpy_more memcpy_byte
+    RET.W
+memcpy_byte:
+    LOAD r3 r1 0
+    STORE r0 0 r3
+    RADDI r0 1
+    RADDI r1 1
+    RSUBI r2 1
+    JUMP.W memcpy
+
+.bss
+memcpy_more:
+    .zero 1
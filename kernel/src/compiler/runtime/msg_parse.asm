@@ -0,0 +1,25 @@
+# msg_parse: split the message RECV wrote at address r0
+# Returns the source universe in r1, the type in r2, the payload length
+# in r3 and the payload address in r0
+
+.global msg_parse
+
+.text
+msg_parse:
+    # Source is 16-bit little-endian; doubling eight times shifts the
+    # high byte into place
+    LOAD r1 r0 1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    RADD r1 r1
+    LOAD r2 r0 0
+    RADD r1 r2
+    LOAD r2 r0 2
+    LOAD r3 r0 3
+    RADDI r0 4
+    RET.W
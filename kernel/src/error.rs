@@ -83,6 +83,38 @@ pub enum KernelError {
         message: String,
     },
 
+    /// Processor fault raised by a universe's program
+    #[error("Processor fault at ip={ip}: {fault}")]
+    ProcessorFault {
+        /// Fault kind
+        fault: crate::universe::isa::ProcessorFault,
+        /// Instruction pointer of the faulting instruction
+        ip: usize,
+    },
+
+    /// Program rejected by the bytecode verifier
+    #[error("Invalid program: {report}")]
+    InvalidProgram {
+        /// Verification findings
+        report: crate::universe::verifier::VerificationReport,
+    },
+
+    /// Malformed or unloadable program image
+    #[error("Invalid program image: {message}")]
+    InvalidImage {
+        /// Error message
+        message: String,
+    },
+
+    /// Page backing store failure
+    #[error("Page store error on page {page}: {message}")]
+    PageStoreError {
+        /// Physical page ID
+        page: u64,
+        /// Error message
+        message: String,
+    },
+
     /// Generic kernel error
     #[error("Kernel error: {message}")]
     Generic {
@@ -114,6 +146,10 @@ impl KernelError {
             KernelError::InteractionNotFound { .. } => 4,
             KernelError::InvalidCoupling { .. } => 6,
             KernelError::StateVectorError { .. } => 7,
+            KernelError::ProcessorFault { .. } => 8,
+            KernelError::PageStoreError { .. } => 7,
+            KernelError::InvalidProgram { .. } => 6,
+            KernelError::InvalidImage { .. } => 6,
             KernelError::Generic { .. } => 5,
         }
     }
@@ -139,4 +175,24 @@ mod tests {
         assert!(!not_found.is_law_violation());
         assert_eq!(not_found.severity(), 4);
     }
+
+    #[test]
+    fn test_new_variant_severity_ranks() {
+        let message = || "test".to_string();
+        let blocked = KernelError::EvolutionBlocked { pressure: 1.0, resistance: 2.0 }.severity();
+        let corrupted = KernelError::StateVectorError { message: message() }.severity();
+        let law = KernelError::EntropyDecrease { previous: 2.0, current: 1.0, delta: -1.0 }.severity();
+
+        let fault = KernelError::ProcessorFault { fault: crate::universe::isa::ProcessorFault::InvalidOpcode, ip: 0 };
+        let store = KernelError::PageStoreError { page: 1, message: message() };
+        let program = KernelError::InvalidProgram { report: Default::default() };
+        let image = KernelError::InvalidImage { message: message() };
+
+        // Refusing to load ranks with an invalid coupling, above a blocked evolution
+        assert_eq!(program.severity(), image.severity());
+        assert!(image.severity() > blocked);
+        // Page store failures are state corruption; faults rank above it, below the laws
+        assert_eq!(store.severity(), corrupted);
+        assert!(corrupted < fault.severity() && fault.severity() < law);
+    }
 }
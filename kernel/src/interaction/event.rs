@@ -18,7 +18,7 @@ impl fmt::Display for EventID {
 }
 
 /// Type of causal event
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventType {
     /// Pure energy transfer (like heat)
     EnergyTransfer,
@@ -38,6 +38,37 @@ pub enum EventType {
     Cancellation,
 }
 
+impl EventType {
+    /// Numeric code seen by programs (0 is reserved for "no event")
+    pub fn code(self) -> u8 {
+        match self {
+            EventType::EnergyTransfer => 1,
+            EventType::Signal => 2,
+            EventType::Entangle => 3,
+            EventType::Observation => 4,
+            EventType::Reversion => 5,
+            EventType::Branch => 6,
+            EventType::StateMigration => 7,
+            EventType::Cancellation => 8,
+        }
+    }
+
+    /// Event type for a numeric code
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(EventType::EnergyTransfer),
+            2 => Some(EventType::Signal),
+            3 => Some(EventType::Entangle),
+            4 => Some(EventType::Observation),
+            5 => Some(EventType::Reversion),
+            6 => Some(EventType::Branch),
+            7 => Some(EventType::StateMigration),
+            8 => Some(EventType::Cancellation),
+            _ => None,
+        }
+    }
+}
+
 /// A Causal Event - "The Photon"
 ///
 /// Represents a packet of influence traveling through an interaction.
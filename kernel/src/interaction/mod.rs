@@ -1,4 +1,5 @@
 //! Interaction management module
+#[allow(clippy::module_inception)]
 pub mod interaction;
 pub mod event;
 pub mod field;
@@ -49,6 +49,18 @@ pub mod constants {
     
     /// Minimum entropy increase per evolution step
     pub const MIN_ENTROPY_DELTA: f64 = 0.0001;
+
+    /// Maximum number of undelivered events queued in a universe's inbox
+    pub const MAX_INBOX_EVENTS: usize = 64;
+
+    /// Evolution steps without access before a page is compressed to ground state
+    pub const PAGE_COLD_STEPS: u64 = 32;
+
+    /// Instructions granted per unit of scheduler velocity in a time slice
+    pub const INSTRUCTIONS_PER_VELOCITY: f64 = 16.0;
+
+    /// Upper bound on instructions a universe executes per evolution step
+    pub const MAX_SLICE_INSTRUCTIONS: u32 = 256;
 }
 
 #[cfg(test)]
@@ -2,7 +2,6 @@
 
 use env_logger::Env;
 use paradox_kernel::{Kernel, Observer};
-use parala_compiler;
 use std::thread;
 use std::time::Duration;
 
@@ -16,7 +15,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("╔════════════════════════════════════════╗");
     println!("║     🌌 ParadoxOS Kernel v{:<13}║", paradox_kernel::VERSION);
-    println!("║  Node Mode: {}:{} -> {} ║", "127.0.0.1", listen_port, remote_port);
+    println!("║  Node Mode: 127.0.0.1:{} -> {} ║", listen_port, remote_port);
     println!("╚════════════════════════════════════════╝\n");
 
     // Big Bang - Initialize kernel
@@ -57,19 +56,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📅 Loading Scheduler Service...");
     let scheduler_code = r#"
         # Scheduler - Pure Computation
-        .def counter 200
         .def limit 100
-        .def temp 201
-        
+
+        .bss
+        counter:
+            .zero 1
+        temp:
+            .zero 1
+
+        .text
         SET counter 0
         loop:
-            ADD counter 1
-            CMP counter limit temp
+            ADDI counter 1
+            CMPI counter limit temp
             JUMPIF temp loop
         HALT
     "#;
     
-    let scheduler_bytecode = paradox_kernel::compiler::assemble(&scheduler_code)
+    let scheduler_bytecode = paradox_kernel::compiler::assemble(scheduler_code)
         .expect("Scheduler compilation failed");
     kernel.load_program(u3, scheduler_bytecode)?;
     println!("   ✓ Scheduler loaded into U3");
@@ -78,19 +82,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📬 Loading Message Router...");
     let router_code = r#"
         # Router - Internal Processing
-        .def msg_count 200
         .def max_msgs 50
-        .def temp 201
-        
+
+        .bss
+        msg_count:
+            .zero 1
+        temp:
+            .zero 1
+
+        .text
         SET msg_count 0
         route_loop:
-            ADD msg_count 1
-            CMP msg_count max_msgs temp
+            ADDI msg_count 1
+            CMPI msg_count max_msgs temp
             JUMPIF temp route_loop
         HALT
     "#;
     
-    let router_bytecode = paradox_kernel::compiler::assemble(&router_code)
+    let router_bytecode = paradox_kernel::compiler::assemble(router_code)
         .expect("Router compilation failed");
     kernel.load_program(u4, router_bytecode)?;
     println!("   ✓ Router loaded into U4");
@@ -99,22 +108,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔍 Loading System Monitor...");
     let monitor_code = r#"
         # Monitor - Health Tracking
-        .def health 200
-        .def checks 201
-        .def temp 202
-        
+        .bss
+        health:
+            .zero 1
+        checks:
+            .zero 1
+        temp:
+            .zero 1
+
+        .text
         SET health 100
         SET checks 0
         
         monitor_loop:
-            SUB health 1
-            ADD checks 1
-            CMP health 0 temp
+            SUBI health 1
+            ADDI checks 1
+            CMPI health 0 temp
             JUMPIF temp monitor_loop
         HALT
     "#;
     
-    let monitor_bytecode = paradox_kernel::compiler::assemble(&monitor_code)
+    let monitor_bytecode = paradox_kernel::compiler::assemble(monitor_code)
         .expect("Monitor compilation failed");
     kernel.load_program(u5, monitor_bytecode)?;
     println!("   ✓ Monitor loaded into U5");
@@ -134,7 +148,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             OBSERVE scheduler_id 0 energy_addr
             
             # 2. Compare energy with threshold
-            CMP energy_addr threshold is_low
+            CMPI energy_addr threshold is_low
             
             # 3. If energy is high, jump to entangle (CMP result 1 means energy > threshold)
             JUMPIF is_low entangle_phase
@@ -148,7 +162,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             BRANCH 100 102
             
             # 6. Signal the new universe (ID stored at 102)
-            SIGNAL 102 "Awaken"
+            SIGNAL @102 "Awaken"
 
             # 7. Phase 17: Potentialize old state to reduce gravity
             # MemSwap: virtual_addr
@@ -167,11 +181,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🏭 Compiling Parala Orchestrator Service...");
     let orchestrator_src = include_str!("../../services/orchestrator.para");
     
-    let orchestrator_bytecode = parala_compiler::compile(&orchestrator_src)
+    let orchestrator_image = parala_compiler::compile_image(orchestrator_src)
         .expect("Parala compilation failed");
 
-    let u7 = kernel.spawn_universe(200.0)?;
-    kernel.load_program(u7, orchestrator_bytecode)?;
+    // The image asks for the energy declared in its universe block
+    let u7 = kernel.spawn_universe(orchestrator_image.energy.max(200.0))?;
+    kernel.load_image(u7, &orchestrator_image)?;
     println!("   ✓ Orchestrator (Parala-Native) loaded into U7");
 
     println!("\n🌌 ParadoxOS Multi-Service System Ready! (Phase 11)");
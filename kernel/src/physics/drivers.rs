@@ -1,5 +1,6 @@
 use anyhow::Result;
 use crate::types::UniverseID;
+use crate::universe::memory::PagePool;
 use ratatui::{
     backend::CrosstermBackend,
     widgets::{Block, Borders, Paragraph, List, ListItem, Gauge},
@@ -26,7 +27,7 @@ pub enum SystemPulse {
 /// Hardware Abstraction Layer (HAL) for ParadoxOS
 pub trait HardwareDriver {
     fn name(&self) -> &str;
-    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse>;
+    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, pages: &mut PagePool, incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse>;
     fn handle_event(&mut self, event: &crate::interaction::CausalEvent) -> Result<()>;
     fn pending_energy(&self) -> f64 { 0.0 }
 }
@@ -66,7 +67,7 @@ impl HardwareDriver for TuiDashboardDriver {
         "Paradox Dashboard"
     }
 
-    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _pages: &mut PagePool, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
         // Check for user input
         let mut pulse = SystemPulse::None;
         if crossterm::event::poll(std::time::Duration::from_millis(0))? {
@@ -188,6 +189,21 @@ impl HardwareDriver for TuiDashboardDriver {
     }
 }
 
+/// What the archive driver persists: the universes and the pages they map
+#[derive(Debug, serde::Deserialize)]
+pub struct MultiverseArchive {
+    pub universes: hashbrown::HashMap<UniverseID, crate::universe::Universe>,
+    /// Every page resident, so the archive does not depend on a page store
+    pub page_pool: PagePool,
+}
+
+/// Borrowed form of `MultiverseArchive`, written without cloning the universes
+#[derive(serde::Serialize)]
+struct ArchiveRef<'a> {
+    universes: &'a hashbrown::HashMap<UniverseID, crate::universe::Universe>,
+    page_pool: &'a PagePool,
+}
+
 /// A driver that persists the Multiverse state to disk
 pub struct ArchiveDriver {
     path: std::path::PathBuf,
@@ -203,32 +219,48 @@ impl ArchiveDriver {
             archive_interval: std::time::Duration::from_secs(5), // Save every 5 seconds
         }
     }
-}
 
-impl HardwareDriver for ArchiveDriver {
-    fn name(&self) -> &str {
-        "Multiverse Archive (Disk)"
+    fn plf_path(&self) -> std::path::PathBuf {
+        self.path.with_extension("plf")
     }
 
-    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
-        if self.last_archive.elapsed() < self.archive_interval {
-            return Ok(SystemPulse::None);
-        }
-        self.last_archive = std::time::Instant::now();
-
+    /// Write the universes and their pages to the archive now
+    pub fn archive(&self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, pages: &mut PagePool) -> Result<()> {
         // Serialize the universes to JSON
-        let json_data = serde_json::to_vec(universes)?;
+        let page_pool = pages.resident_copy();
+        let json_data = serde_json::to_vec(&ArchiveRef { universes, page_pool: &page_pool })?;
         
         // Phase 6: Compress using ParadoxLF (Memory as Potential)
         let compressed_data = paradoxlf::compress(&json_data);
         
         // Save as .plf (Paradox Lossless Fluid)
-        let plf_path = self.path.with_extension("plf");
+        let plf_path = self.plf_path();
         std::fs::write(&plf_path, &compressed_data)?;
         
         let ratio = paradoxlf::compression_ratio(json_data.len(), compressed_data.len());
         log::info!("💾 Multiverse Archived (Ratio: {:.2}x) to {}", ratio, plf_path.display());
-        
+        Ok(())
+    }
+
+    /// Read the last archive back
+    pub fn load(&self) -> Result<MultiverseArchive> {
+        let compressed_data = std::fs::read(self.plf_path())?;
+        let json_data = paradoxlf::decompress(&compressed_data, None).map_err(anyhow::Error::msg)?;
+        Ok(serde_json::from_slice(&json_data)?)
+    }
+}
+
+impl HardwareDriver for ArchiveDriver {
+    fn name(&self) -> &str {
+        "Multiverse Archive (Disk)"
+    }
+
+    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, pages: &mut PagePool, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+        if self.last_archive.elapsed() < self.archive_interval {
+            return Ok(SystemPulse::None);
+        }
+        self.last_archive = std::time::Instant::now();
+        self.archive(universes, pages)?;
         Ok(SystemPulse::None)
     }
 
@@ -254,7 +286,7 @@ pub enum NetworkMessage {
         event: crate::interaction::CausalEvent,
     },
     SyncState {
-        universe: crate::universe::Universe,
+        universe: Box<crate::universe::Universe>,
     },
 }
 
@@ -335,15 +367,12 @@ impl HardwareDriver for WormholeDriver {
         "Wormhole Driver (Network)"
     }
 
-    fn sync(&mut self, _universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+    fn sync(&mut self, _universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _pages: &mut PagePool, incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
         // Collect messages from background task
         while let Ok(msg) = self.incoming_rx.try_recv() {
-            match msg {
-                NetworkMessage::Event { event } => {
-                    log::info!("🛸 Photon materialized from wormhole: U{} -> U{}", event.source, event.target);
-                    incoming_events.push(event);
-                }
-                _ => {}
+            if let NetworkMessage::Event { event } = msg {
+                log::info!("🛸 Photon materialized from wormhole: U{} -> U{}", event.source, event.target);
+                incoming_events.push(event);
             }
         }
         Ok(SystemPulse::None)
@@ -407,7 +436,7 @@ impl WebGatewayDriver {
 
 impl HardwareDriver for WebGatewayDriver {
     fn name(&self) -> &str { "Web Monitoring Gateway" }
-    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _pages: &mut PagePool, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
         let json = serde_json::to_string(universes).unwrap_or_default();
         let state_ref = self.state_json.clone();
         self.runtime.spawn(async move {
@@ -442,12 +471,18 @@ impl KineticEnergyDriver {
     }
 }
 
+impl Default for KineticEnergyDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HardwareDriver for KineticEnergyDriver {
     fn name(&self) -> &str {
         "Kinetic Energy Channel (CPU)"
     }
 
-    fn sync(&mut self, _universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+    fn sync(&mut self, _universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _pages: &mut PagePool, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
         self.sys.refresh_cpu_usage();
         
         let mut total_usage = 0.0;
@@ -481,16 +516,16 @@ impl ChaosMonkeyDriver {
 impl HardwareDriver for ChaosMonkeyDriver {
     fn name(&self) -> &str { "Chaos Monkey (Entropy Injection)" }
 
-    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
+    fn sync(&mut self, universes: &hashbrown::HashMap<UniverseID, crate::universe::Universe>, _pages: &mut PagePool, _incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> Result<SystemPulse> {
         self.step_count += 1;
         
         // Only act every 5 steps
-        if self.step_count % 5 != 0 {
+        if !self.step_count.is_multiple_of(5) {
             return Ok(SystemPulse::None);
         }
 
         // High intensity network flood every 10 steps
-        if self.step_count % 10 == 0 && self.intensity > 0.7 {
+        if self.step_count.is_multiple_of(10) && self.intensity > 0.7 {
             log::info!("🐒 Chaos Monkey: Triggering High-Frequency Network Flash...");
             // Spawning many small events via the kernel would be better, 
             // but for now we'll just log that the monkey is messing with the fabric.
@@ -511,3 +546,37 @@ impl HardwareDriver for ChaosMonkeyDriver {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::page_store::DiskPageStore;
+
+    #[test]
+    fn test_archive_round_trip_keeps_mapped_pages() {
+        let dir = std::env::temp_dir().join(format!("paradox-archive-{}", std::process::id()));
+        let mut pool = PagePool::new();
+        pool.set_store(Some(Box::new(DiskPageStore::open(dir.join("pages")).unwrap())));
+        pool.resident_budget = Some(0);
+
+        let mut universe = crate::universe::Universe::new(UniverseID(1), 100.0);
+        universe.memory.allocate_page(&mut pool, 2);
+        universe.memory.write_byte(&mut pool, 512 + 7, 42).unwrap();
+        universe.memory.allocate_page(&mut pool, 3);
+        universe.memory.write_byte(&mut pool, 768, 43).unwrap();
+        // Evict one page so the archive has to read it back from the store
+        universe.memory.swap_to_ground_state(&mut pool, 768);
+        assert_eq!(pool.enforce_budget(), 1);
+
+        let mut universes = hashbrown::HashMap::new();
+        universes.insert(universe.id, universe);
+        let driver = ArchiveDriver::new(dir.join("multiverse_archive.json"));
+        driver.archive(&universes, &mut pool).unwrap();
+
+        let mut archive = driver.load().unwrap();
+        let memory = &mut archive.universes.get_mut(&UniverseID(1)).unwrap().memory;
+        assert_eq!(memory.read_byte(&mut archive.page_pool, 512 + 7).map(|(b, _)| b), Some(42));
+        assert_eq!(memory.read_byte(&mut archive.page_pool, 768).map(|(b, _)| b), Some(43));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,44 @@
+//! Processor Fault Policy
+//!
+//! Decides what happens to a universe whose program raised a processor fault.
+
+use crate::universe::isa::FaultRecord;
+use crate::universe::Universe;
+
+/// Kernel response to a processor fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Stop executing the universe (its state is kept for inspection)
+    Halt,
+    /// Collapse the universe, returning its energy to the pool
+    Collapse,
+    /// Resume execution at a handler address in the universe
+    Trap {
+        /// Handler address within the universe's state
+        handler: usize,
+    },
+}
+
+/// Policy hook consulted by the kernel whenever a universe faults
+pub trait FaultPolicy {
+    fn on_fault(&self, universe: &Universe, record: &FaultRecord) -> FaultAction;
+}
+
+impl<F> FaultPolicy for F
+where
+    F: Fn(&Universe, &FaultRecord) -> FaultAction,
+{
+    fn on_fault(&self, universe: &Universe, record: &FaultRecord) -> FaultAction {
+        self(universe, record)
+    }
+}
+
+/// Default policy: halt the faulting universe
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HaltOnFault;
+
+impl FaultPolicy for HaltOnFault {
+    fn on_fault(&self, _universe: &Universe, _record: &FaultRecord) -> FaultAction {
+        FaultAction::Halt
+    }
+}
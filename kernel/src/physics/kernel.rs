@@ -4,10 +4,16 @@ use crate::constants::*;
 use crate::error::{KernelError, Result};
 use crate::interaction::Interaction;
 use crate::types::{InteractionID, UniverseID};
-use crate::universe::Universe;
+use crate::universe::{isa, EnergyCostModel, ExecutionState, Universe};
+use crate::universe::memory::{PagePool, PagingStats};
+use crate::universe::page_store::PageStore;
+use crate::universe::verifier;
+use super::faults::FaultAction;
 use super::laws;  // laws is a sibling module in physics/
+use super::scheduler::SliceMetrics;
 use super::security;
 use hashbrown::HashMap;
+use paradoxef::{ProgramImage, SectionKind};
 use log::{debug, info, warn};
 use std::collections::VecDeque;
 
@@ -18,6 +24,7 @@ pub struct KernelSnapshot {
     pub global_entropy: f64,
     pub universes: HashMap<crate::types::UniverseID, crate::universe::Universe>,
     pub interactions: HashMap<crate::types::InteractionID, Interaction>,
+    pub page_pool: PagePool,
     pub evolution_step: u64,
     pub energy_radiated: f64,
     pub energy_materialized: f64,
@@ -74,6 +81,24 @@ pub struct Kernel {
 
     /// Gravity-Based Scheduler (Phase 18)
     scheduler: super::scheduler::GravityScheduler,
+
+    /// Response to processor faults
+    fault_policy: Box<dyn super::faults::FaultPolicy>,
+
+    /// Physical pages mapped by universes (Phase 17)
+    page_pool: PagePool,
+
+    /// Energy charged per executed instruction
+    cost_model: EnergyCostModel,
+
+    /// Time slices granted in the last evolution step
+    slice_metrics: HashMap<UniverseID, SliceMetrics>,
+
+    /// Instructions executed since the Big Bang
+    instructions_executed: u64,
+
+    /// Load programs that fail verification (with a warning) instead of rejecting them
+    allow_unverified_programs: bool,
 }
 
 impl Kernel {
@@ -106,6 +131,12 @@ impl Kernel {
             energy_radiated: 0.0,
             energy_materialized: 0.0,
             scheduler: super::scheduler::GravityScheduler::new(),
+            fault_policy: Box::new(super::faults::HaltOnFault),
+            page_pool: PagePool::new(),
+            cost_model: EnergyCostModel::default(),
+            slice_metrics: HashMap::new(),
+            instructions_executed: 0,
+            allow_unverified_programs: false,
         }
     }
 
@@ -114,6 +145,37 @@ impl Kernel {
         self.drivers.push(driver);
     }
 
+    /// Evict ground-state pages to a backing store once the resident set
+    /// exceeds `resident_budget` bytes
+    pub fn set_page_store(&mut self, store: Box<dyn PageStore>, resident_budget: usize) {
+        self.page_pool.set_store(Some(store));
+        self.page_pool.resident_budget = Some(resident_budget);
+    }
+
+    /// Replace the energy cost model used to execute programs
+    ///
+    /// Fails, keeping the current model, if any cost is negative or non-finite.
+    pub fn set_cost_model(&mut self, model: EnergyCostModel) -> Result<()> {
+        model.validate()?;
+        self.cost_model = model;
+        Ok(())
+    }
+
+    /// Energy cost model used to execute programs
+    pub fn cost_model(&self) -> &EnergyCostModel {
+        &self.cost_model
+    }
+
+    /// Load programs that fail verification, with a warning, instead of rejecting them
+    pub fn set_allow_unverified_programs(&mut self, allow: bool) {
+        self.allow_unverified_programs = allow;
+    }
+
+    /// Replace the policy deciding how processor faults are handled
+    pub fn set_fault_policy(&mut self, policy: Box<dyn super::faults::FaultPolicy>) {
+        self.fault_policy = policy;
+    }
+
     /// Spawn a new universe
     ///
     /// # Arguments
@@ -185,7 +247,10 @@ impl Kernel {
         let mut branched = {
             let parent = self.universes.get_mut(&parent_id)
                 .ok_or(KernelError::UniverseNotFound { id: parent_id })?;
-            parent.branch(new_id)?
+            let mut branched = parent.branch(new_id)?;
+            // Inherited pages stay shared until either side writes to them
+            parent.memory.share_with_branch(&mut branched.memory, &mut self.page_pool);
+            branched
         };
         
         // Set creation time to current step (LAW 7)
@@ -284,14 +349,65 @@ impl Kernel {
         Ok(id)
     }
 
-    /// Load a program (Universal Bytecode) into a universe
+    /// Load a program into a universe
+    ///
+    /// `code` is either raw Universal Bytecode, which becomes the state vector
+    /// from address 0, or a serialized ParadoxEF image.
     pub fn load_program(&mut self, universe_id: UniverseID, code: Vec<u8>) -> Result<()> {
+        let image = if ProgramImage::is_image(&code) {
+            ProgramImage::from_bytes(&code)
+                .map_err(|e| KernelError::InvalidImage { message: e.to_string() })?
+        } else {
+            ProgramImage::from_code(code)
+        };
+        self.load_image(universe_id, &image)
+    }
+
+    /// Lay a program image out in a universe's state vector
+    pub fn load_image(&mut self, universe_id: UniverseID, image: &ProgramImage) -> Result<()> {
         let universe = self.universes.get_mut(&universe_id)
             .ok_or(KernelError::UniverseNotFound { id: universe_id })?;
-            
-        // Overwrite state vector with raw executable code
-        universe.state_vector = crate::types::StateVector::new_raw(code);
-        universe.instruction_pointer = 0;
+
+        // Images built in memory skip the parser's check
+        if !image.energy.is_finite() || image.energy < 0.0 {
+            return Err(KernelError::InvalidImage { message: paradoxef::ImageError::BadEnergy.to_string() });
+        }
+        if image.energy > universe.energy {
+            return Err(KernelError::InsufficientEnergy {
+                requested: image.energy,
+                available: universe.energy,
+            });
+        }
+
+        // Catch malformed code before it burns energy
+        for section in image.sections.iter().filter(|s| s.kind == SectionKind::Code) {
+            let report = verifier::verify_at(&section.data, section.addr as usize);
+            if report.is_valid() {
+                continue;
+            }
+            if !self.allow_unverified_programs {
+                return Err(KernelError::InvalidProgram { report });
+            }
+            warn!("⚠️ Loading unverified program into {:?}: {}", universe_id, report);
+        }
+
+        let mut memory = image.layout()
+            .map_err(|e| KernelError::InvalidImage { message: e.to_string() })?;
+
+        // Pad so the 8-bit data area is always addressable
+        if memory.len() < isa::NARROW_ADDRESS_SPACE {
+            memory.resize(isa::NARROW_ADDRESS_SPACE, 0);
+        }
+        universe.state_vector = crate::types::StateVector::new_raw(memory);
+        universe.stack = match image.stack_size {
+            0 => isa::CallStack::default(),
+            depth => isa::CallStack::new(depth as usize),
+        };
+        universe.handlers.clear();
+        universe.handler_return = None;
+        universe.instruction_pointer = image.entry as usize;
+        universe.last_fault = None;
+        universe.execution_state = ExecutionState::Running;
         
         info!("💾 Program loaded into {:?}", universe_id);
         Ok(())
@@ -331,8 +447,12 @@ impl Kernel {
         self.propagate_events();
 
         // Step 4: Evolve universes
+        self.page_pool.clock = self.evolution_step;
         self.evolve_universes();
 
+        // Step 4.5: Return cold pages to ground state (LAW 8)
+        self.swap_out_cold_pages();
+
         // Step 5: Collapse unstable universes
         self.collapse_unstable_universes();
 
@@ -375,6 +495,7 @@ impl Kernel {
             global_entropy: self.global_entropy,
             universes: self.universes.clone(),
             interactions: self.interactions.clone(),
+            page_pool: self.page_pool.snapshot(),
             evolution_step: self.evolution_step,
             energy_radiated: self.energy_radiated,
             energy_materialized: self.energy_materialized,
@@ -382,7 +503,9 @@ impl Kernel {
         
         self.history.push_back(snapshot);
         if self.history.len() > 100 {
-            self.history.pop_front();
+            if let Some(expired) = self.history.pop_front() {
+                self.page_pool.unpin(&expired.page_pool);
+            }
         }
     }
 
@@ -400,12 +523,16 @@ impl Kernel {
             self.global_entropy = snapshot.global_entropy;
             self.universes = snapshot.universes;
             self.interactions = snapshot.interactions;
+            // The backing store outlives the timeline it is rewound from
+            self.page_pool.rewind_to(snapshot.page_pool);
             self.evolution_step = snapshot.evolution_step;
             self.energy_radiated = snapshot.energy_radiated;
             self.energy_materialized = snapshot.energy_materialized;
             
             // Truncate history forward
-            self.history.truncate(target_index);
+            for dropped in self.history.drain(target_index..) {
+                self.page_pool.unpin(&dropped.page_pool);
+            }
             
             true
         } else {
@@ -417,7 +544,7 @@ impl Kernel {
     fn sync_drivers(&mut self, incoming_events: &mut Vec<crate::interaction::CausalEvent>) -> super::drivers::SystemPulse {
         let mut combined_pulse = super::drivers::SystemPulse::None;
         for driver in &mut self.drivers {
-            match driver.sync(&self.universes, incoming_events) {
+            match driver.sync(&self.universes, &mut self.page_pool, incoming_events) {
                 Ok(pulse) => {
                     if pulse != super::drivers::SystemPulse::None {
                         combined_pulse = pulse;
@@ -527,10 +654,20 @@ impl Kernel {
                 info!("📬 Event {} ({:?}) delivered to {} (Data: {} bytes, E={:.2}J)", 
                       event.id, event.event_type, event.target, 
                       event.data.size(), event.energy_payload);
-                      
-                // In a full implementation, `target.handle_event(event)` would be called here
-                // to update internal state (LAW 0).
-                // For now, energy conservation is the primary effect.
+
+                // Queue the data for the program (LAW 0); RECV/WAIT consume it
+                let (id, target_id, event_type) = (event.id, event.target, event.event_type);
+                if !target.deliver(event) {
+                    warn!("📪 Inbox of {} full, event {} dropped", target_id, id);
+                    continue;
+                }
+
+                // Interrupt the program if it registered a handler
+                let dispatch_cost = self.cost_model.handler_dispatch;
+                if target.dispatch_handler(event_type, dispatch_cost) {
+                    self.global_energy += dispatch_cost; // Dispatch heat (Law 1)
+                    debug!("🔔 {} entered its {:?} handler", target_id, event_type);
+                }
             }
         }
     }
@@ -550,6 +687,8 @@ impl Kernel {
         let updates = self.scheduler.next_tasks(self.universes.len());
 
         let mut generated_events = Vec::new();
+        let mut to_collapse = Vec::new();
+        self.slice_metrics.clear();
 
         // Apply evolution updates
         for (id, rate) in updates {
@@ -565,21 +704,53 @@ impl Kernel {
 
                 universe.last_evolution = self.evolution_step;
 
-                // Phase 5: Execution
-                let (event, execution_cost) = universe.execute_step();
-                
-                // Add execution heat to global energy (Law 1: Energy Conservation)
-                // The cost was deducted from the universe, so it goes to the global pool
-                self.global_energy += execution_cost;
-                
-                if let Some(e) = event {
-                    generated_events.push(e);
+                // Phase 5: Execution, one time slice per step
+                let budget = laws::instruction_budget(rate, universe.interaction_density());
+                universe.slice_budget = budget;
+                let mut executed = 0;
+                while executed < budget && universe.is_running() && universe.energy >= 0.001 {
+                    executed += 1;
+                    match universe.execute_step(&mut self.page_pool, &self.cost_model) {
+                        Ok((event, execution_cost)) => {
+                            if let Some(code) = universe.exit_code() {
+                                info!("🏁 Universe {} halted (exit code {})", id, code);
+                            }
+
+                            // Add execution heat to global energy (Law 1: Energy Conservation)
+                            // The cost was deducted from the universe, so it goes to the global pool
+                            self.global_energy += execution_cost;
+
+                            // A system event ends the slice so its effects land before
+                            // the next instruction runs
+                            if let Some(e) = event {
+                                generated_events.push(e);
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("⚠️ Universe {} faulted: {}", id, e);
+                            if let Some(record) = universe.last_fault {
+                                match self.fault_policy.on_fault(universe, &record) {
+                                    FaultAction::Halt => {}
+                                    FaultAction::Collapse => to_collapse.push(id),
+                                    FaultAction::Trap { handler } => universe.enter_trap(handler),
+                                }
+                            }
+                            break;
+                        }
+                    }
                 }
+                self.slice_metrics.insert(id, SliceMetrics { budget, executed });
+                self.instructions_executed += executed as u64;
 
                 debug!("🌀 Universe {} evolved (rate={:.2})", id, rate);
             }
         }
 
+        for id in to_collapse {
+            let _ = self.collapse_universe(id);
+        }
+
         // Route generated events
         for event in generated_events {
             if let Err(e) = self.route_event(event) {
@@ -601,17 +772,32 @@ impl Kernel {
             crate::interaction::EventType::Observation => {
                 // Synchronous metadata query
                 if let Some(target) = self.universes.get(&event.target) {
-                    let meta_type = event.data.raw()[0];
-                    let dest_addr = event.data.raw()[1] as usize;
-                    let val = match meta_type {
-                        0 => (target.energy / 10.0) as u8,
-                        1 => (target.entropy / 10.0) as u8,
-                        2 => (target.stability_score * 255.0) as u8,
-                        _ => 0,
+                    let data = event.data.raw();
+                    let meta_type = data[0];
+                    let dest_addr = u16::from_le_bytes([data[1], data[2]]) as usize;
+                    let width = data.get(3).copied().unwrap_or(1) as usize;
+                    let bytes = if width == 4 {
+                        // OBSERVE32: full precision in thousandths
+                        let val = match meta_type {
+                            0 => target.energy,
+                            1 => target.entropy,
+                            2 => target.stability_score,
+                            _ => 0.0,
+                        };
+                        ((val * isa::FIXED_POINT_SCALE) as u32).to_le_bytes().to_vec()
+                    } else {
+                        let val = match meta_type {
+                            0 => (target.energy / 10.0) as u8,
+                            1 => (target.entropy / 10.0) as u8,
+                            2 => (target.stability_score * 255.0) as u8,
+                            _ => 0,
+                        };
+                        vec![val]
                     };
                     if let Some(source) = self.universes.get_mut(&event.source) {
-                        if dest_addr < source.state_vector.raw().len() {
-                             source.state_vector.raw_mut()[dest_addr] = val;
+                        let state = source.state_vector.raw_mut();
+                        if dest_addr + bytes.len() <= state.len() {
+                             state[dest_addr..dest_addr + bytes.len()].copy_from_slice(&bytes);
                         }
                     }
                 }
@@ -626,20 +812,36 @@ impl Kernel {
             }
             crate::interaction::EventType::Branch => {
                 let energy = event.energy_payload;
-                let dest_addr = event.data.raw()[0] as usize;
-                if let Ok(new_id) = self.branch_universe(event.source) {
+                let data = event.data.raw();
+                let dest_addr = u16::from_le_bytes([data[0], data[1]]) as usize;
+                let id_len = data[2] as usize;
+                // Refuse to branch rather than hand back a truncated child id
+                let child = if self.next_universe_id >= 1 << (8 * id_len) {
+                    warn!(
+                        "🌿 Universe id {} does not fit the {}-byte BRANCH destination of {}",
+                        self.next_universe_id, id_len, event.source
+                    );
+                    None
+                } else {
+                    // Branching fails when the parent is low on energy
+                    self.branch_universe(event.source).ok()
+                };
+                match child {
                     // Inject initial energy if available
-                    if energy > 0.0 {
+                    Some(new_id) if energy > 0.0 => {
                         let _ = self.inject_energy(new_id, energy);
                     }
-                    if let Some(source) = self.universes.get_mut(&event.source) {
-                        if dest_addr < source.state_vector.raw().len() {
-                             source.state_vector.raw_mut()[dest_addr] = new_id.0 as u8;
-                        }
+                    Some(_) => {}
+                    // Return the payload to the global pool
+                    None => self.global_energy += energy,
+                }
+                // A refused branch reads back as 0, which is never a universe id
+                if let Some(source) = self.universes.get_mut(&event.source) {
+                    let state = source.state_vector.raw_mut();
+                    if dest_addr + id_len <= state.len() {
+                         state[dest_addr..dest_addr + id_len]
+                             .copy_from_slice(&child.map_or(0, |id| id.0).to_le_bytes()[..id_len]);
                     }
-                } else {
-                    // Branching failed (likely low energy), return payload to global
-                    self.global_energy += energy;
                 }
                 return Ok(());
             }
@@ -676,6 +878,21 @@ impl Kernel {
         Ok(())
     }
 
+    fn swap_out_cold_pages(&mut self) {
+        let mut swapped = 0;
+        for universe in self.universes.values_mut() {
+            swapped += universe.memory.swap_out_cold(&mut self.page_pool, PAGE_COLD_STEPS);
+        }
+        let evicted = self.page_pool.enforce_budget();
+        if swapped + evicted > 0 {
+            // Shared pages change mass for every universe mapping them
+            for universe in self.universes.values_mut() {
+                universe.memory.update_mass(&self.page_pool);
+            }
+            debug!("🧊 {} cold pages returned to ground state, {} evicted", swapped, evicted);
+        }
+    }
+
     fn collapse_unstable_universes(&mut self) {
         let mut to_collapse = Vec::new();
 
@@ -697,10 +914,13 @@ impl Kernel {
     /// - LAW 1: Energy returned to pool
     /// - LAW 2: Entropy released
     pub fn collapse_universe(&mut self, id: UniverseID) -> Result<Universe> {
-        let universe = self.universes.remove(&id).ok_or(
+        let mut universe = self.universes.remove(&id).ok_or(
             KernelError::UniverseNotFound { id }
         )?;
 
+        // Pages no other universe maps are reclaimed (Law 8)
+        universe.memory.release_all(&mut self.page_pool);
+
         info!("💥 Universe {} collapsed (stability={:.2})", id, universe.stability_score);
 
         // Return energy to global pool (LAW 1)
@@ -810,6 +1030,26 @@ impl Kernel {
         self.interactions.len()
     }
 
+    /// Exit code of a halted universe
+    pub fn exit_code(&self, id: UniverseID) -> Option<u8> {
+        self.universes.get(&id).and_then(|u| u.exit_code())
+    }
+
+    /// Time slice a universe received in the last evolution step
+    pub fn slice_metrics(&self, id: UniverseID) -> Option<SliceMetrics> {
+        self.slice_metrics.get(&id).copied()
+    }
+
+    /// Total instructions executed since the Big Bang
+    pub fn instructions_executed(&self) -> u64 {
+        self.instructions_executed
+    }
+
+    /// Swap-in/swap-out counters of a universe's pages
+    pub fn paging_stats(&self, id: UniverseID) -> Option<PagingStats> {
+        self.universes.get(&id).map(|u| u.memory.stats)
+    }
+
     /// Get universe reference
     pub fn get_universe(&self, id: UniverseID) -> Option<&Universe> {
         self.universes.get(&id)
@@ -839,6 +1079,40 @@ mod tests {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    /// Spawn a universe driving `targets`, so the energy gradient gives them
+    /// an instruction budget every step
+    fn pump(kernel: &mut Kernel, targets: &[UniverseID]) {
+        let pump = kernel.spawn_universe(900.0).unwrap();
+        for &target in targets {
+            kernel.create_interaction(pump, target, 0.9).unwrap();
+        }
+    }
+
+    /// A kernel with one pumped universe holding `energy`, and 1100 J to spare
+    fn pumped(energy: f64) -> (Kernel, UniverseID) {
+        init_logger();
+        let mut kernel = Kernel::new(energy + 2000.0);
+        let u = kernel.spawn_universe(energy).unwrap();
+        pump(&mut kernel, &[u]);
+        (kernel, u)
+    }
+
+    /// Step the kernel until `done` holds, failing the test after 100 steps
+    fn run_until(kernel: &mut Kernel, mut done: impl FnMut(&Kernel) -> bool) {
+        for _ in 0..100 {
+            if done(kernel) {
+                return;
+            }
+            kernel.evolution_step();
+        }
+        assert!(done(kernel), "kernel did not reach the expected state within 100 steps");
+    }
+
+    /// Whether a universe has run its program to HALT
+    fn exited(kernel: &Kernel, id: UniverseID) -> bool {
+        kernel.get_universe(id).is_some_and(|u| u.exit_code().is_some())
+    }
+
     #[test]
     fn test_kernel_creation() {
         init_logger();
@@ -852,7 +1126,7 @@ mod tests {
         init_logger();
         let mut kernel = Kernel::new(1000.0);
         
-        let u1 = kernel.spawn_universe(100.0).unwrap();
+        kernel.spawn_universe(100.0).unwrap();
         assert_eq!(kernel.global_energy(), 900.0);
         assert!(kernel.global_entropy() > 0.0); // Entropy increased
     }
@@ -913,4 +1187,516 @@ mod tests {
         let total = kernel.calculate_total_energy();
         assert!((total - 5000.0).abs() < ENERGY_EPSILON);
     }
+
+    #[test]
+    fn test_set_cost_model_rejects_invalid_prices() {
+        let mut kernel = Kernel::new(1000.0);
+        let mut negative = EnergyCostModel::default();
+        negative.opcodes.insert(isa::OpCode::Signal, crate::universe::costs::OpCost::new(-1.0, 0.0));
+        assert!(kernel.set_cost_model(negative).is_err());
+        let nan = EnergyCostModel { erasure_per_byte: f64::NAN, ..Default::default() };
+        assert!(kernel.set_cost_model(nan).is_err());
+        assert_eq!(kernel.cost_model(), &EnergyCostModel::default());
+
+        let cheap = EnergyCostModel { cycle: 0.0, ..Default::default() };
+        kernel.set_cost_model(cheap.clone()).unwrap();
+        assert_eq!(kernel.cost_model(), &cheap);
+    }
+
+    #[test]
+    fn test_fault_policy() {
+        init_logger();
+        let mut kernel = Kernel::new(2000.0);
+        let halted = kernel.spawn_universe(100.0).unwrap();
+        let trapped = kernel.spawn_universe(100.0).unwrap();
+        pump(&mut kernel, &[halted, trapped]);
+
+        // DIV.W 40 41 with a zero divisor
+        kernel.load_program(halted, vec![0xFE, 0x61, 40, 0, 41, 0]).unwrap();
+        // DIV 40 41, then a handler at 3 storing the fault code: RSTORE 42 r7; HALT
+        kernel.load_program(trapped, vec![0x61, 40, 41, 0x32, 42, 7, 0xFF]).unwrap();
+
+        kernel.set_fault_policy(Box::new(move |u: &Universe, _: &isa::FaultRecord| {
+            if u.id == trapped { FaultAction::Trap { handler: 3 } } else { FaultAction::Halt }
+        }));
+        run_until(&mut kernel, |k| {
+            k.get_universe(halted).is_some_and(|u| u.execution_state == ExecutionState::Faulted)
+                && exited(k, trapped)
+        });
+
+        let u = kernel.get_universe(halted).unwrap();
+        assert_eq!(u.execution_state, ExecutionState::Faulted);
+        assert_eq!(u.instruction_pointer, 0);
+        let record = u.last_fault.unwrap();
+        assert_eq!(record.fault, isa::ProcessorFault::DivideByZero);
+        assert_eq!((record.opcode, record.wide), (0x61, true)); // The opcode, not the WIDE prefix
+
+        let u = kernel.get_universe(trapped).unwrap();
+        assert_eq!(u.exit_code(), Some(0)); // Handler ran to HALT
+        assert_eq!(u.state_vector.raw()[42], isa::ProcessorFault::DivideByZero.code());
+
+        kernel.set_fault_policy(Box::new(|_: &Universe, _: &isa::FaultRecord| FaultAction::Collapse));
+        assert!(matches!(kernel.load_program(halted, vec![0xEE]), Err(KernelError::InvalidProgram { .. })));
+        kernel.set_allow_unverified_programs(true);
+        kernel.load_program(halted, vec![0xEE]).unwrap();
+        kernel.evolution_step();
+        assert!(kernel.get_universe(halted).is_none());
+    }
+
+    #[test]
+    fn test_load_program_image() {
+        let (mut kernel, u) = pumped(100.0);
+        let poor = kernel.spawn_universe(30.0).unwrap();
+
+        let image = crate::compiler::assemble(r#"
+            .entry main
+            .energy 50
+            .data
+            counter:
+                .byte 41
+            .text
+            skipped:
+                HALT
+            main:
+                ADDI counter 1
+                HALT
+        "#).unwrap();
+        assert!(matches!(kernel.load_program(poor, image.clone()), Err(KernelError::InsufficientEnergy { .. })));
+        kernel.load_program(u, image).unwrap();
+        assert_eq!(kernel.get_universe(u).unwrap().instruction_pointer, 1);
+
+        run_until(&mut kernel, |k| exited(k, u));
+        let universe = kernel.get_universe(u).unwrap();
+        assert_eq!(universe.exit_code(), Some(0));
+        assert_eq!(universe.state_vector.raw()[5], 42); // Data section follows the code
+
+        // Energy requests must be real amounts
+        let mut image = ProgramImage::from_code(vec![0xFF]);
+        image.energy = f64::NAN;
+        assert!(matches!(kernel.load_image(u, &image), Err(KernelError::InvalidImage { .. })));
+        image.energy = 0.0;
+
+        // Sections must not overlap
+        image.sections.push(image.sections[0].clone());
+        assert!(matches!(kernel.load_image(u, &image), Err(KernelError::InvalidImage { .. })));
+
+        // The image sizes the call stack
+        image.sections.pop();
+        image.stack_size = 16;
+        kernel.load_image(u, &image).unwrap();
+        assert_eq!(kernel.get_universe(u).unwrap().stack.depth(), 16);
+    }
+
+    #[test]
+    fn test_linked_program_calls_runtime() {
+        let (mut kernel, u) = pumped(200.0);
+
+        let main = crate::compiler::assemble_object("main.pasm", r#"
+            .extern memcpy checksum msg_parse
+            .global copy sum
+            .entry main
+            .text
+            main:
+                RSET r0 copy
+                RSET r1 bytes
+                RSET r2 4
+                CALL.W memcpy
+                RSET r0 copy
+                RSET r1 4
+                CALL.W checksum
+                RSTORE sum r2
+                RSET r0 message
+                CALL.W msg_parse
+                HALT
+            .rodata
+            bytes:
+                .byte 1 2 3 250
+            message:
+                .byte 2 1 9 3
+            .bss
+            copy:
+                .zero 4
+            sum:
+                .zero 1
+        "#).unwrap();
+        let image = crate::compiler::link_with_runtime(&[main]).unwrap();
+        kernel.load_image(u, &image).unwrap();
+
+        run_until(&mut kernel, |k| exited(k, u));
+        let universe = kernel.get_universe(u).unwrap();
+        assert_eq!(universe.exit_code(), Some(0));
+        let copy = image.symbol("copy").unwrap() as usize;
+        assert_eq!(&universe.state_vector.raw()[copy..copy + 4], &[1, 2, 3, 250]);
+        assert_eq!(universe.state_vector.raw()[image.symbol("sum").unwrap() as usize], 0); // 256 wraps
+        assert_eq!(&universe.registers[1..4], &[258, 9, 3]);
+    }
+
+    #[test]
+    fn test_signal_reaches_receiver_inbox() {
+        init_logger();
+        let mut kernel = Kernel::new(1000.0);
+        let sender = kernel.spawn_universe(300.0).unwrap();
+        let receiver = kernel.spawn_universe(100.0).unwrap();
+        kernel.create_interaction(sender, receiver, 0.9).unwrap();
+
+        let sender_code = crate::compiler::assemble(&format!("SIGNAL {} \"hi\"\nHALT", receiver.0)).unwrap();
+        let receiver_code = crate::compiler::assemble("WAIT 0\nRECV 100 16\nHALT").unwrap();
+        kernel.load_program(sender, sender_code).unwrap();
+        kernel.load_program(receiver, receiver_code).unwrap();
+
+        run_until(&mut kernel, |k| exited(k, receiver));
+
+        let u = kernel.get_universe(receiver).unwrap();
+        assert_eq!(u.exit_code(), Some(0));
+        assert_eq!(&u.state_vector.raw()[100..106], &[sender.0 as u8, 0, 2, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn test_signal_interrupts_into_handler() {
+        init_logger();
+        let mut kernel = Kernel::new(1000.0);
+        let sender = kernel.spawn_universe(300.0).unwrap();
+        let receiver = kernel.spawn_universe(100.0).unwrap();
+        kernel.create_interaction(sender, receiver, 0.9).unwrap();
+
+        let sender_code = crate::compiler::assemble(&format!("SIGNAL {} \"hi\"\nHALT", receiver.0)).unwrap();
+        let receiver_code = crate::compiler::assemble(r#"
+            HANDLER 2 on_signal
+            spin:
+                ADDI 90 1
+                JUMP spin
+            on_signal:
+                RECV 100 8
+                ADDI 91 1
+                IRET
+        "#).unwrap();
+        kernel.load_program(sender, sender_code).unwrap();
+        kernel.load_program(receiver, receiver_code).unwrap();
+
+        run_until(&mut kernel, |k| {
+            k.get_universe(receiver).is_some_and(|u| u.state_vector.raw()[91] == 1 && u.handler_return.is_none())
+        });
+
+        let u = kernel.get_universe(receiver).unwrap();
+        assert!(u.is_running());
+        assert_eq!(u.handler_return, None); // Handler returned
+        assert_eq!(u.state_vector.raw()[91], 1);
+        assert_eq!(&u.state_vector.raw()[100..106], &[sender.0 as u8, 0, 2, 2, b'h', b'i']);
+        assert!(u.instruction_pointer < 8); // Back in the loop
+    }
+
+    #[test]
+    fn test_self_reports_time_slice_budget() {
+        let (mut kernel, u) = pumped(100.0);
+
+        kernel.load_program(u, crate::compiler::assemble("SELF 5 100\nSELF 0 104\nHALT").unwrap()).unwrap();
+        run_until(&mut kernel, |k| k.slice_metrics(u).is_some());
+
+        let budget = kernel.slice_metrics(u).unwrap().budget;
+        let state = kernel.get_universe(u).unwrap().state_vector.raw();
+        assert_eq!(u32::from_le_bytes(state[100..104].try_into().unwrap()), budget);
+        let energy = u32::from_le_bytes(state[104..108].try_into().unwrap()) as f64 / isa::FIXED_POINT_SCALE;
+        assert!(energy > 1.0);
+    }
+
+    #[test]
+    fn test_branch_then_signal_child() {
+        let (mut kernel, parent) = pumped(300.0);
+
+        let code = crate::compiler::assemble(r#"
+            BRANCH 50 100
+            ENTANGLE @100 255
+            SIGNAL @100 "hi"
+            HALT
+        "#).unwrap();
+        kernel.load_program(parent, code).unwrap();
+
+        run_until(&mut kernel, |k| exited(k, parent));
+
+        let state = kernel.get_universe(parent).unwrap().state_vector.raw();
+        let child = UniverseID(u16::from_le_bytes([state[100], state[101]]) as u64);
+        assert_ne!(child, parent);
+        let message = kernel.get_universe(child).unwrap().inbox.iter()
+            .find(|e| e.source == parent)
+            .expect("child should receive the parent's signal");
+        assert_eq!(message.data.expand(), b"hi");
+    }
+
+    #[test]
+    fn test_branch_id_width() {
+        let (mut kernel, parent) = pumped(3000.0);
+
+        let code = crate::compiler::assemble("BRANCH 50 100\nBRANCH.W 50 102\nHALT").unwrap();
+        kernel.load_program(parent, code).unwrap();
+        kernel.universes.get_mut(&parent).unwrap().state_vector.raw_mut()[101] = 0xAA;
+
+        run_until(&mut kernel, |k| exited(k, parent));
+
+        // Narrow BRANCH writes one byte, BRANCH.W two
+        let state = kernel.get_universe(parent).unwrap().state_vector.raw();
+        assert_eq!(state[100], 3);
+        assert_eq!(state[101], 0xAA);
+        assert_eq!(&state[102..104], &[4, 0]);
+
+        // An id that does not fit a narrow destination is refused, not truncated
+        kernel.next_universe_id = 256;
+        let code = crate::compiler::assemble("BRANCH 50 100\nHALT").unwrap();
+        kernel.load_program(parent, code).unwrap();
+        kernel.universes.get_mut(&parent).unwrap().state_vector.raw_mut()[100] = 0xBB;
+        let count = kernel.universes.len();
+        run_until(&mut kernel, |k| exited(k, parent));
+        assert_eq!(kernel.universes.len(), count);
+        assert_eq!(kernel.get_universe(parent).unwrap().state_vector.raw()[100], 0);
+    }
+
+    #[test]
+    fn test_branch_without_energy_reports_zero() {
+        let (mut kernel, parent) = pumped(3000.0);
+        kernel.load_program(parent, crate::compiler::assemble("HALT").unwrap()).unwrap();
+        let universe = kernel.universes.get_mut(&parent).unwrap();
+        universe.energy = 1.0;
+        universe.state_vector.raw_mut()[100..102].copy_from_slice(&[0xBB, 0xBB]);
+        let count = kernel.universes.len();
+        let pool = kernel.global_energy;
+
+        let event = crate::interaction::CausalEvent {
+            id: crate::interaction::EventID(1),
+            event_type: crate::interaction::EventType::Branch,
+            source: parent,
+            target: UniverseID(0),
+            energy_payload: 20.0,
+            data: crate::types::StateVector::from_raw(vec![100, 0, 2]),
+            creation_step: 0,
+            cause_id: None,
+        };
+        // Same convention as an id that does not fit: no error, a zero id, the payload refunded
+        kernel.route_event(event).unwrap();
+        assert_eq!(kernel.universes.len(), count);
+        assert_eq!(&kernel.get_universe(parent).unwrap().state_vector.raw()[100..102], &[0, 0]);
+        assert_eq!(kernel.global_energy, pool + 20.0);
+    }
+
+    #[test]
+    fn test_map_page_from_another_universe() {
+        let (mut kernel, reader) = pumped(300.0);
+        let owner = kernel.spawn_universe(300.0).unwrap();
+
+        let owner_memory = &mut kernel.universes.get_mut(&owner).unwrap().memory;
+        let page = owner_memory.allocate_page(&mut kernel.page_pool, 4);
+        owner_memory.write_byte(&mut kernel.page_pool, 4 * 256 + 3, 42).unwrap();
+        assert_eq!(owner_memory.share_page(&mut kernel.page_pool, 4, reader), Some(page));
+
+        let code = crate::compiler::assemble("MEMMAP.W 512 r1\nHALT").unwrap();
+        kernel.load_program(reader, code).unwrap();
+        kernel.universes.get_mut(&reader).unwrap().registers[1] = page.0 as u32;
+
+        run_until(&mut kernel, |k| exited(k, reader));
+
+        // Both universes see the same physical page
+        let reader_memory = &mut kernel.universes.get_mut(&reader).unwrap().memory;
+        assert_eq!(reader_memory.page_table.get(&2), Some(&page));
+        reader_memory.write_byte(&mut kernel.page_pool, 512 + 3, 43).unwrap();
+        let owner_memory = &mut kernel.universes.get_mut(&owner).unwrap().memory;
+        assert_eq!(owner_memory.read_byte(&mut kernel.page_pool, 4 * 256 + 3).map(|(b, _)| b), Some(43));
+        assert_eq!(kernel.page_pool.pages[&page].entanglement_count, 2);
+
+        // The page outlives its owner until the last mapping goes
+        kernel.collapse_universe(owner).unwrap();
+        assert_eq!(kernel.page_pool.pages[&page].entanglement_count, 1);
+        kernel.collapse_universe(reader).unwrap();
+        assert!(kernel.page_pool.is_empty());
+    }
+
+    #[test]
+    fn test_branch_keeps_mapped_pages_entangled() {
+        init_logger();
+        let mut kernel = Kernel::new(2000.0);
+        let owner = kernel.spawn_universe(300.0).unwrap();
+        let reader = kernel.spawn_universe(300.0).unwrap();
+
+        let page = kernel.universes.get_mut(&owner).unwrap().memory.allocate_page(&mut kernel.page_pool, 4);
+        let reader_memory = &mut kernel.universes.get_mut(&reader).unwrap().memory;
+        assert!(reader_memory.map_page(&mut kernel.page_pool, 2, page));
+
+        // Neither side of the branch copies the mapped page on write
+        let child = kernel.branch_universe(reader).unwrap();
+        let reader_memory = &mut kernel.universes.get_mut(&reader).unwrap().memory;
+        reader_memory.write_byte(&mut kernel.page_pool, 512 + 3, 43).unwrap();
+        assert_eq!(reader_memory.page_table[&2], page);
+        let owner_memory = &mut kernel.universes.get_mut(&owner).unwrap().memory;
+        assert_eq!(owner_memory.read_byte(&mut kernel.page_pool, 4 * 256 + 3).map(|(b, _)| b), Some(43));
+        owner_memory.write_byte(&mut kernel.page_pool, 4 * 256 + 3, 44).unwrap();
+        let child_memory = &mut kernel.universes.get_mut(&child).unwrap().memory;
+        assert_eq!(child_memory.read_byte(&mut kernel.page_pool, 512 + 3).map(|(b, _)| b), Some(44));
+        assert_eq!(kernel.page_pool.pages[&page].entanglement_count, 3);
+        assert_eq!(kernel.page_pool.len(), 1);
+    }
+
+    #[test]
+    fn test_branch_copies_pages_on_write() {
+        init_logger();
+        let mut kernel = Kernel::new(2000.0);
+        let parent = kernel.spawn_universe(300.0).unwrap();
+
+        let parent_memory = &mut kernel.universes.get_mut(&parent).unwrap().memory;
+        let page = parent_memory.allocate_page(&mut kernel.page_pool, 2);
+        parent_memory.write_byte(&mut kernel.page_pool, 512, 1).unwrap();
+
+        let child = kernel.branch_universe(parent).unwrap();
+        assert_eq!(kernel.page_pool.pages[&page].entanglement_count, 2);
+
+        // The child's first write lands in a private copy
+        let child_memory = &mut kernel.universes.get_mut(&child).unwrap().memory;
+        let (_, energy) = child_memory.write_byte(&mut kernel.page_pool, 512, 2).unwrap();
+        assert!(energy > 0.25);
+        assert_ne!(child_memory.page_table[&2], page);
+        assert_eq!(kernel.page_pool.len(), 2);
+
+        // The parent still sees its own data and now owns the page alone
+        let parent_memory = &mut kernel.universes.get_mut(&parent).unwrap().memory;
+        assert_eq!(parent_memory.read_byte(&mut kernel.page_pool, 512).map(|(b, _)| b), Some(1));
+        assert_eq!(kernel.page_pool.pages[&page].entanglement_count, 1);
+        parent_memory.write_byte(&mut kernel.page_pool, 512, 3).unwrap();
+        assert_eq!(parent_memory.page_table[&2], page);
+    }
+
+    #[test]
+    fn test_cold_pages_swap_out_and_back_in() {
+        init_logger();
+        let mut kernel = Kernel::new(2000.0);
+        let id = kernel.spawn_universe(300.0).unwrap();
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        let page = memory.allocate_page(&mut kernel.page_pool, 2);
+        let excited_mass = memory.total_mass();
+
+        for _ in 0..PAGE_COLD_STEPS + 1 {
+            kernel.evolution_step();
+        }
+
+        assert!(kernel.page_pool.pages[&page].content.is_compressed);
+        assert_eq!(kernel.paging_stats(id).unwrap(), PagingStats { swap_ins: 0, swap_outs: 1 });
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        assert!(memory.total_mass() < excited_mass);
+
+        // The next access expands the page and pays the excitation energy
+        let (_, energy) = memory.read_byte(&mut kernel.page_pool, 512).unwrap();
+        assert!(energy > kernel.page_pool.pages[&page].mass);
+        assert_eq!(memory.total_mass(), excited_mass);
+        assert_eq!(kernel.paging_stats(id).unwrap().swap_ins, 1);
+    }
+
+    #[test]
+    fn test_ground_state_pages_evict_to_disk() {
+        init_logger();
+        let dir = std::env::temp_dir().join(format!("paradox-kernel-pages-{}", std::process::id()));
+        let mut kernel = Kernel::new(2000.0);
+        kernel.set_page_store(Box::new(crate::universe::page_store::DiskPageStore::open(&dir).unwrap()), 0);
+        let id = kernel.spawn_universe(300.0).unwrap();
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        let page = memory.allocate_page(&mut kernel.page_pool, 2);
+        memory.write_byte(&mut kernel.page_pool, 600, 9).unwrap();
+
+        for _ in 0..PAGE_COLD_STEPS + 1 {
+            kernel.evolution_step();
+        }
+
+        assert!(kernel.page_pool.pages[&page].evicted.is_some());
+        assert_eq!(kernel.page_pool.evictions, 1);
+        assert_eq!(kernel.page_pool.resident_bytes(), 0);
+
+        // Faulting the page back in restores its contents
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        assert_eq!(memory.read_byte(&mut kernel.page_pool, 600).map(|(b, _)| b), Some(9));
+        assert_eq!(kernel.page_pool.restores, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rewind_restores_evicted_pages() {
+        init_logger();
+        let dir = std::env::temp_dir().join(format!("paradox-kernel-rewind-{}", std::process::id()));
+        let mut kernel = Kernel::new(2000.0);
+        kernel.set_page_store(Box::new(crate::universe::page_store::DiskPageStore::open(&dir).unwrap()), 0);
+        let id = kernel.spawn_universe(300.0).unwrap();
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        let page = memory.allocate_page(&mut kernel.page_pool, 2);
+        memory.write_byte(&mut kernel.page_pool, 600, 9).unwrap();
+
+        for _ in 0..PAGE_COLD_STEPS + 1 {
+            kernel.evolution_step();
+        }
+        assert!(kernel.page_pool.pages[&page].evicted.is_some());
+        let mark = kernel.history.len();
+
+        // Overwrite the page and let it be evicted again over the first copy
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        memory.write_byte(&mut kernel.page_pool, 600, 10).unwrap();
+        for _ in 0..PAGE_COLD_STEPS + 1 {
+            kernel.evolution_step();
+        }
+        assert_eq!(kernel.page_pool.evictions, 2);
+
+        // The rewound timeline reads the bytes it had, not the store's latest
+        assert!(kernel.rewind(kernel.history.len() - mark + 1));
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        assert_eq!(memory.read_byte(&mut kernel.page_pool, 600).map(|(b, _)| b), Some(9));
+
+        // Once the page is gone and no snapshot pins its versions, the store is emptied
+        kernel.collapse_universe(id).unwrap();
+        for _ in 0..=100 {
+            kernel.evolution_step();
+        }
+        assert_eq!(kernel.page_pool.stored_versions(), 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_history_keeps_evicted_pages_out_of_memory() {
+        init_logger();
+        let dir = std::env::temp_dir().join(format!("paradox-kernel-history-{}", std::process::id()));
+        let budget = 0;
+        let mut kernel = Kernel::new(2000.0);
+        kernel.set_page_store(Box::new(crate::universe::page_store::DiskPageStore::open(&dir).unwrap()), budget);
+        let id = kernel.spawn_universe(300.0).unwrap();
+        let memory = &mut kernel.universes.get_mut(&id).unwrap().memory;
+        for v_page in 2..5 {
+            memory.allocate_page(&mut kernel.page_pool, v_page);
+            memory.write_byte(&mut kernel.page_pool, v_page * 256, v_page as u8).unwrap();
+        }
+
+        for _ in 0..PAGE_COLD_STEPS + 1 {
+            kernel.evolution_step();
+        }
+        assert_eq!(kernel.page_pool.evictions, 3);
+        let mark = kernel.history.len();
+        for _ in 0..5 {
+            kernel.evolution_step();
+        }
+
+        // Snapshots taken after eviction refer to the store instead of holding the pages
+        let total: usize = kernel.history.range(mark..)
+            .map(|snapshot| snapshot.page_pool.resident_bytes())
+            .sum::<usize>() + kernel.page_pool.resident_bytes();
+        assert!(total <= budget);
+        assert_eq!(kernel.page_pool.stored_versions(), 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_time_slice_runs_several_instructions() {
+        let (mut kernel, worker) = pumped(300.0);
+
+        let code = crate::compiler::assemble("spin:\nRADDI r0 1\nJUMP spin").unwrap();
+        kernel.load_program(worker, code).unwrap();
+
+        run_until(&mut kernel, |k| k.slice_metrics(worker).is_some());
+
+        let slice = kernel.slice_metrics(worker).expect("worker should be scheduled");
+        assert_eq!(slice.executed, slice.budget); // Nothing ends the slice early
+        assert!(slice.executed > 1);
+        let counter = kernel.get_universe(worker).unwrap().registers[0];
+        assert_eq!(counter, slice.executed.div_ceil(2));
+        assert!(kernel.instructions_executed() >= slice.executed as u64);
+    }
 }
@@ -3,9 +3,9 @@
 use crate::constants::*;
 use crate::error::{KernelError, Result};
 
-/// Law enforcement functions
-///
-/// These functions verify that kernel operations comply with the 13 fundamental laws
+// Law enforcement functions
+//
+// These functions verify that kernel operations comply with the 13 fundamental laws
 
 /// LAW 0: Existence - All entities must have state
 pub fn verify_existence<T>(entity: &Option<T>, name: &str) -> Result<()> {
@@ -50,9 +50,9 @@ pub fn verify_entropy_increase(previous: f64, current: f64) -> Result<()> {
     }
 }
 
-/// LAW 3: Interaction Primacy
-///
-/// Verified at compile time by type system - universes can only communicate via Interaction
+// LAW 3: Interaction Primacy
+//
+// Verified at compile time by type system - universes can only communicate via Interaction
 
 /// LAW 4: Force-Resistance Velocity
 ///
@@ -70,6 +70,15 @@ pub fn calculate_time_dilation(interaction_density: f64) -> f64 {
     1.0 / (1.0 + interaction_density)
 }
 
+/// Instruction budget for one time slice (LAW 4 + LAW 7)
+///
+/// Faster-evolving universes (velocity = pressure / resistance) run more
+/// instructions per step; dense interaction dilates their local time.
+pub fn instruction_budget(velocity: f64, interaction_density: f64) -> u32 {
+    let budget = velocity.max(0.0) * calculate_time_dilation(interaction_density) * INSTRUCTIONS_PER_VELOCITY;
+    (budget.ceil() as u32).clamp(1, MAX_SLICE_INSTRUCTIONS)
+}
+
 /// LAW 9: Stability and Collapse
 ///
 /// Check if universe should collapse
@@ -84,13 +93,13 @@ pub fn detect_anomaly(expected: f64, actual: f64) -> bool {
     (expected - actual).abs() > ENERGY_EPSILON
 }
 
-/// LAW 13: Forbidden Concepts
-///
-/// These are enforced at code review / compile time:
-/// - No std::thread usage
-/// - No Arc<Mutex<T>> patterns
-/// - No global clocks (std::time::Instant)
-/// - No unsafe blocks (already denied in lib.rs)
+// LAW 13: Forbidden Concepts
+//
+// These are enforced at code review / compile time:
+// - No std::thread usage
+// - No Arc<Mutex<T>> patterns
+// - No global clocks (std::time::Instant)
+// - No unsafe blocks (already denied in lib.rs)
 
 #[cfg(test)]
 mod tests {
@@ -126,6 +135,14 @@ mod tests {
         assert!(dilated < 0.2);
     }
 
+    #[test]
+    fn test_instruction_budget() {
+        assert_eq!(instruction_budget(0.0, 0.0), 1);
+        assert!(instruction_budget(2.0, 0.0) > instruction_budget(1.0, 0.0));
+        assert!(instruction_budget(1.0, 4.0) < instruction_budget(1.0, 0.0));
+        assert_eq!(instruction_budget(1e9, 0.0), MAX_SLICE_INSTRUCTIONS);
+    }
+
     #[test]
     fn test_collapse_detection() {
         assert!(should_collapse(0.2)); // Below threshold
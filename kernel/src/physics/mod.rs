@@ -4,7 +4,9 @@ pub mod observer;
 pub mod drivers;
 pub mod security;
 pub mod scheduler;
+pub mod faults;
 
 pub use kernel::Kernel;
 pub use observer::Observer;
 pub use drivers::HardwareDriver;
+pub use faults::{FaultAction, FaultPolicy};
@@ -51,7 +51,7 @@ impl Observer {
         let unstable = self.predict_instability(kernel);
         for id in unstable {
             // AGI injects energy to stabilize
-            if let Ok(_) = kernel.inject_energy(id, 5.0) {
+            if kernel.inject_energy(id, 5.0).is_ok() {
                  info!("   🛡️ AGI: Deploying Stabilization Pulse to U{} (Restoring Causality)", id);
                  if let Some(u) = kernel.get_universe_mut(id) {
                      u.stability_score = (u.stability_score + 0.15).min(1.0);
@@ -14,16 +14,25 @@ impl Eq for CausalTask {}
 
 impl PartialOrd for CausalTask {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.priority.partial_cmp(&other.priority)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for CausalTask {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+        self.priority.partial_cmp(&other.priority).unwrap_or(Ordering::Equal)
     }
 }
 
+/// Instructions granted and used by a universe in one evolution step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SliceMetrics {
+    /// Instruction budget of the slice
+    pub budget: u32,
+    /// Instructions actually executed
+    pub executed: u32,
+}
+
 /// Gravity-Based Scheduler (Phase 18)
 ///
 /// Prioritizes universes based on 'Causal Momentum'.
@@ -32,6 +41,12 @@ pub struct GravityScheduler {
     task_queue: BinaryHeap<CausalTask>,
 }
 
+impl Default for GravityScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GravityScheduler {
     pub fn new() -> Self {
         Self {
@@ -61,6 +76,10 @@ impl GravityScheduler {
     pub fn schedule(&mut self, universes: &hashbrown::HashMap<UniverseID, Universe>, pressures: &hashbrown::HashMap<UniverseID, f64>) {
         self.task_queue.clear();
         for (id, u) in universes {
+            // Halted, blocked and faulted universes do not evolve
+            if !u.is_running() {
+                continue;
+            }
             let pressure = pressures.get(id).copied().unwrap_or(0.0);
             let priority = Self::calculate_priority(u, pressure);
             if priority > 0.0001 { // Lower threshold for high-pressure situations
@@ -0,0 +1,192 @@
+//! Thermodynamic cost model for the Universal ISA
+//!
+//! Every instruction pays the cycle cost plus its opcode's `OpCost`. Writes
+//! that change bytes additionally pay the erasure cost (LAW 1: bit erasure).
+//! Models are plain JSON so deployments can tune them without recompiling.
+
+use super::isa::OpCode;
+use crate::error::{KernelError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Energy cost of one opcode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpCost {
+    /// Flat cost per execution
+    #[serde(default)]
+    pub base: f64,
+    /// Cost per byte the instruction moves or touches
+    #[serde(default)]
+    pub per_byte: f64,
+}
+
+impl OpCost {
+    pub const fn new(base: f64, per_byte: f64) -> Self {
+        Self { base, per_byte }
+    }
+
+    /// Energy for an execution touching `bytes` bytes
+    pub fn charge(&self, bytes: usize) -> f64 {
+        self.base + self.per_byte * bytes as f64
+    }
+}
+
+/// Per-opcode energy costs, attachable to a `Kernel`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnergyCostModel {
+    /// Base thermodynamic cost of every instruction cycle
+    pub cycle: f64,
+    /// Cost per byte whose value a write changes
+    pub erasure_per_byte: f64,
+    /// Cost of interrupting a program to run an event handler
+    pub handler_dispatch: f64,
+    /// Opcode costs; opcodes missing here cost nothing beyond the cycle
+    pub opcodes: HashMap<OpCode, OpCost>,
+}
+
+impl Default for EnergyCostModel {
+    fn default() -> Self {
+        Self {
+            cycle: 0.0001,
+            erasure_per_byte: 0.01,
+            handler_dispatch: 0.005,
+            opcodes: (0..=u8::MAX)
+                .filter_map(OpCode::from_u8)
+                .map(|op| (op, default_cost(op)))
+                .collect(),
+        }
+    }
+}
+
+impl EnergyCostModel {
+    /// Parse a model from JSON
+    ///
+    /// Fields and opcodes the JSON leaves out keep their default costs.
+    /// Negative or non-finite costs are rejected: they would let programs
+    /// draw energy from the global pool or poison the energy accounting.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut model: Self = serde_json::from_str(json).map_err(|e| KernelError::Generic {
+            message: format!("invalid energy cost model: {}", e),
+        })?;
+        for (op, cost) in Self::default().opcodes {
+            model.opcodes.entry(op).or_insert(cost);
+        }
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Fail unless every cost is a finite, non-negative amount of energy
+    pub(crate) fn validate(&self) -> Result<()> {
+        let mut costs = vec![
+            ("cycle".to_string(), self.cycle),
+            ("erasure_per_byte".to_string(), self.erasure_per_byte),
+            ("handler_dispatch".to_string(), self.handler_dispatch),
+        ];
+        for (op, cost) in &self.opcodes {
+            costs.push((format!("{:?}.base", op), cost.base));
+            costs.push((format!("{:?}.per_byte", op), cost.per_byte));
+        }
+        match costs.into_iter().find(|(_, value)| !value.is_finite() || *value < 0.0) {
+            Some((name, value)) => Err(KernelError::Generic {
+                message: format!("invalid energy cost model: {} is {}, costs must be finite and non-negative", name, value),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Load a model from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| KernelError::Generic {
+            message: format!("cannot read energy cost model {}: {}", path.display(), e),
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Serialize the model as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Cost entry for an opcode
+    pub fn op(&self, op: OpCode) -> OpCost {
+        self.opcodes.get(&op).copied().unwrap_or_default()
+    }
+
+    /// Erasure energy for a write that changed `bytes` bytes
+    pub fn erasure(&self, bytes: usize) -> f64 {
+        self.erasure_per_byte * bytes as f64
+    }
+}
+
+/// Built-in cost of an opcode
+fn default_cost(op: OpCode) -> OpCost {
+    use OpCode::*;
+    match op {
+        // Plain writes only pay for erasure
+        NoOp | Wide | AtomSet | Set16 | Set32 | RegStore | RegStore16 | RegStore32
+        | Store | StoreIdx | StorePtr | JumpIf | Wait | Halt | Exit => OpCost::new(0.0, 0.0),
+        AtomXor | Not => OpCost::new(0.005, 0.0), // Reversible is cheaper
+        AtomCopy | RegLoad | RegLoad16 | RegLoad32 => OpCost::new(0.0, 0.001),
+        Add | Sub | AddImm | SubImm => OpCost::new(0.002, 0.0),
+        Mul => OpCost::new(0.004, 0.0),
+        Div | Mod => OpCost::new(0.006, 0.0),
+        // AND/OR erase information, so they cost like a write
+        And | Or => OpCost::new(0.008, 0.0),
+        Shl | Shr => OpCost::new(0.004, 0.0),
+        Cmp | CmpS | CmpImm | Load | LoadIdx | LoadPtr => OpCost::new(0.001, 0.0),
+        Add16 | Sub16 | Add32 | Sub32 => OpCost::new(0.0, 0.002),
+        Mul16 | Mul32 => OpCost::new(0.0, 0.004),
+        Div16 | Div32 => OpCost::new(0.0, 0.006),
+        Cmp16 | CmpS16 | Cmp32 | CmpS32 => OpCost::new(0.0, 0.001),
+        RegSet => OpCost::new(0.0002, 0.0),
+        RegAdd | RegSub | RegCmp | Jump | Poll | Handler | SelfInfo | SelfInfoReg => OpCost::new(0.0005, 0.0),
+        RegAddImm | RegSubImm | RegCmpImm => OpCost::new(0.0003, 0.0),
+        Call => OpCost::new(0.003, 0.0),
+        Ret | Iret | Push | Pop => OpCost::new(0.002, 0.0),
+        Recv | Signal | SignalTo | SignalInd => OpCost::new(0.001, 0.0001),
+        Entangle | EntangleInd => OpCost::new(5.0, 0.0),
+        Observe | Observe32 | ObserveInd => OpCost::new(0.5, 0.0),
+        Revert => OpCost::new(2.0, 0.0),
+        Branch | BranchInd => OpCost::new(10.0, 0.0),
+        MemAlloc => OpCost::new(0.0, 1.0 / 256.0), // 1 J per 256-byte page
+        MemMap => OpCost::new(2.0, 0.0),
+        MemSwap => OpCost::new(0.5, 0.0),
+        MemShare => OpCost::new(0.5, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_json_keeps_defaults() {
+        let model = EnergyCostModel::from_json(r#"{
+            "cycle": 0.0,
+            "opcodes": { "Branch": { "base": 1.5 } }
+        }"#).unwrap();
+
+        assert_eq!(model.cycle, 0.0);
+        assert_eq!(model.erasure_per_byte, 0.01);
+        assert_eq!(model.op(OpCode::Branch), OpCost::new(1.5, 0.0));
+        assert_eq!(model.op(OpCode::Entangle), OpCost::new(5.0, 0.0));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let model = EnergyCostModel::default();
+        assert_eq!(EnergyCostModel::from_json(&model.to_json()).unwrap(), model);
+        assert!(EnergyCostModel::from_json("{ \"cycle\": \"hot\" }").is_err());
+    }
+
+    #[test]
+    fn test_negative_costs_rejected() {
+        assert!(EnergyCostModel::from_json(r#"{ "cycle": -0.1 }"#).is_err());
+        let err = EnergyCostModel::from_json(r#"{ "opcodes": { "Signal": { "per_byte": -1.0 } } }"#).unwrap_err();
+        assert!(err.to_string().contains("Signal.per_byte is -1"));
+        assert!(EnergyCostModel { cycle: f64::NAN, ..Default::default() }.validate().is_err());
+    }
+}
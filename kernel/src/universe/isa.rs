@@ -4,12 +4,38 @@
 //! in order to execute on ParadoxOS.
 //!
 //! These operations strictly adhere to physics laws (energy costs for bit flips).
+//!
+//! # Encoding Versions
+//!
+//! - **v1**: Every operand is a single byte, so programs can address 256 bytes of state.
+//! - **v2**: An optional `WIDE` prefix byte (0xFE) widens the address operands of the
+//!   following instruction to 16-bit little-endian values, reaching 64 KiB of state.
+//!   v1 bytecode never contains the prefix and runs unchanged.
+//!
+//! # Integer Widths
+//!
+//! The base ALU works on single bytes. Width-suffixed variants (`ADD16`, `CMPS32`...)
+//! operate on little-endian 16- and 32-bit values stored in the state vector; the
+//! 16-bit group lives in the 0x40 page and the 32-bit group in the 0x50 page.
+
+use super::costs::EnergyCostModel;
+use super::memory::{MultiversalMemory, PagePool};
+use super::{ExecutionState, Universe};
+use crate::error::{KernelError, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Current encoding version of the Universal ISA
+pub const ISA_VERSION: u8 = 2;
 
-use crate::error::Result;
-// use crate::types::StateVector;
+/// Number of architectural registers (r0-r7) in each universe
+pub const REGISTER_COUNT: usize = 8;
+
+/// Architectural register file (32-bit, wrapping arithmetic)
+pub type RegisterFile = [u32; REGISTER_COUNT];
 
 /// Universal OpCodes
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum OpCode {
     /// No Operation (burns entropy)
@@ -32,6 +58,15 @@ pub enum OpCode {
     
     /// Compare: CMP [a] [b] [result] - result = 1 if a > b, 0 if equal, 255 if a < b
     Cmp = 0x06,
+
+    /// Add immediate: ADDI [dest] [imm] - dest = dest + imm
+    AddImm = 0x07,
+
+    /// Subtract immediate: SUBI [dest] [imm] - dest = dest - imm
+    SubImm = 0x08,
+
+    /// Compare immediate: CMPI [a] [imm] [result] - same encoding of result as CMP
+    CmpImm = 0x09,
     
     /// Unconditional Jump: JUMP [addr]
     Jump = 0x10,
@@ -39,7 +74,7 @@ pub enum OpCode {
     /// Conditional Jump (if non-zero): JMP_IF [cond_addr] [target]
     JumpIf = 0x11,
     
-    /// Call subroutine: CALL [addr] (pushes return address to stack)
+    /// Call subroutine: CALL [addr] (pushes return address to the call stack, apart from PUSH data)
     Call = 0x20,
     
     /// Return from subroutine: RET (pops return address)
@@ -50,7 +85,156 @@ pub enum OpCode {
     
     /// Pop from stack: POP [addr]
     Pop = 0x23,
+
+    /// Set register: RSET [reg] [imm] - reg = imm
+    RegSet = 0x30,
+
+    /// Load register: RLOAD [reg] [addr] - reg = state[addr]
+    RegLoad = 0x31,
+
+    /// Store register: RSTORE [addr] [reg] - state[addr] = low byte of reg
+    RegStore = 0x32,
+
+    /// Register add: RADD [dest_reg] [src_reg] - dest = dest + src
+    RegAdd = 0x33,
+
+    /// Register subtract: RSUB [dest_reg] [src_reg] - dest = dest - src
+    RegSub = 0x34,
+
+    /// Register compare: RCMP [a_reg] [b_reg] [result] - result encoded as CMP
+    RegCmp = 0x35,
+
+    /// Register add immediate: RADDI [reg] [imm] - reg = reg + imm
+    RegAddImm = 0x36,
+
+    /// Register subtract immediate: RSUBI [reg] [imm] - reg = reg - imm
+    RegSubImm = 0x37,
+
+    /// Register compare immediate: RCMPI [reg] [imm] [result] - result encoded as CMP
+    RegCmpImm = 0x38,
+
+    /// Register-indirect load: LOAD [reg] [ptr_reg] [offset] - reg = mem[ptr_reg + offset]
+    Load = 0x39,
+
+    /// Register-indirect store: STORE [ptr_reg] [offset] [reg] - mem[ptr_reg + offset] = reg
+    Store = 0x3A,
+
+    /// Indexed load: LOADX [reg] [base] [index_reg] - reg = mem[base + index_reg]
+    LoadIdx = 0x3B,
+
+    /// Indexed store: STOREX [base] [index_reg] [reg] - mem[base + index_reg] = reg
+    StoreIdx = 0x3C,
+
+    /// Pointer load: LDP [dest] [ptr] - dest = mem[*ptr] (16-bit LE pointer)
+    LoadPtr = 0x3D,
+
+    /// Pointer store: STP [ptr] [src] - mem[*ptr] = src (16-bit LE pointer)
+    StorePtr = 0x3E,
+
+    /// Multiply: MUL [dest] [src] - dest = dest * src (wrapping)
+    Mul = 0x60,
+
+    /// Divide: DIV [dest] [src] - dest = dest / src, faults on zero divisor
+    Div = 0x61,
+
+    /// Remainder: MOD [dest] [src] - dest = dest % src, faults on zero divisor
+    Mod = 0x62,
+
+    /// Bitwise and: AND [dest] [src] - dest = dest & src
+    And = 0x63,
+
+    /// Bitwise or: OR [dest] [src] - dest = dest | src
+    Or = 0x64,
+
+    /// Shift left: SHL [dest] [bits] - dest = dest << bits
+    Shl = 0x65,
+
+    /// Logical shift right: SHR [dest] [bits] - dest = dest >> bits
+    Shr = 0x66,
+
+    /// Bitwise not (reversible): NOT [dest] - dest = !dest
+    Not = 0x67,
+
+    /// Signed compare: CMPS [a] [b] [result] - bytes as i8, result encoded as CMP
+    CmpS = 0x68,
+
+    /// 16-bit: SET16 [dest] [imm] - store a literal
+    Set16 = 0x40,
+
+    /// 16-bit: ADD16 [dest] [src] - dest = dest + src (wrapping)
+    Add16 = 0x41,
+
+    /// 16-bit: SUB16 [dest] [src] - dest = dest - src (wrapping)
+    Sub16 = 0x42,
+
+    /// 16-bit: MUL16 [dest] [src] - dest = dest * src (wrapping)
+    Mul16 = 0x43,
+
+    /// 16-bit: DIV16 [dest] [src] - unsigned dest = dest / src, faults on zero divisor
+    Div16 = 0x44,
+
+    /// 16-bit: CMP16 [a] [b] [result] - unsigned compare, result encoded as CMP
+    Cmp16 = 0x45,
+
+    /// 16-bit: CMPS16 [a] [b] [result] - signed compare, result encoded as CMP
+    CmpS16 = 0x46,
+
+    /// 16-bit: RLOAD16 [reg] [addr] - reg = value at addr
+    RegLoad16 = 0x47,
+
+    /// 16-bit: RSTORE16 [addr] [reg] - value at addr = reg (truncated)
+    RegStore16 = 0x48,
+
+    /// 32-bit: SET32 [dest] [imm] - store a literal
+    Set32 = 0x50,
+
+    /// 32-bit: ADD32 [dest] [src] - dest = dest + src (wrapping)
+    Add32 = 0x51,
+
+    /// 32-bit: SUB32 [dest] [src] - dest = dest - src (wrapping)
+    Sub32 = 0x52,
+
+    /// 32-bit: MUL32 [dest] [src] - dest = dest * src (wrapping)
+    Mul32 = 0x53,
+
+    /// 32-bit: DIV32 [dest] [src] - unsigned dest = dest / src, faults on zero divisor
+    Div32 = 0x54,
+
+    /// 32-bit: CMP32 [a] [b] [result] - unsigned compare, result encoded as CMP
+    Cmp32 = 0x55,
+
+    /// 32-bit: CMPS32 [a] [b] [result] - signed compare, result encoded as CMP
+    CmpS32 = 0x56,
+
+    /// 32-bit: RLOAD32 [reg] [addr] - reg = value at addr
+    RegLoad32 = 0x57,
+
+    /// 32-bit: RSTORE32 [addr] [reg] - value at addr = reg
+    RegStore32 = 0x58,
     
+    /// Count queued inbox messages: POLL [dest] - dest = min(count, 255)
+    Poll = 0xE0,
+
+    /// Receive the oldest inbox message: RECV [buf] [max_len]
+    /// Writes [source_lo] [source_hi] [type] [len] [payload...] at buf; type 0 = inbox empty
+    Recv = 0xE1,
+
+    /// Block until a message arrives: WAIT [type] (0 = any type)
+    Wait = 0xE2,
+
+    /// Register an event handler: HANDLER [type] [addr] (addr 0 = remove)
+    /// Delivery of a matching event saves the IP and jumps to addr
+    Handler = 0xE3,
+
+    /// Return from an event handler to the interrupted instruction: IRET
+    Iret = 0xE4,
+
+    /// Read a property of the own universe: SELF [field] [dest] - u32 LE at dest
+    SelfInfo = 0xE8,
+
+    /// Read a property of the own universe into a register: SELFR [reg] [field]
+    SelfInfoReg = 0xE9,
+
     /// Emit Signal (interaction): SIGNAL [target_u] [len] [data...]
     Signal = 0xF0,
 
@@ -61,25 +245,216 @@ pub enum OpCode {
     /// 0=Energy, 1=Entropy, 2=Stability
     Observe = 0xF2,
 
+    /// Read metadata at full precision: OBSERVE32 [target_u] [metadata_type] [dest_addr]
+    /// Stores a 32-bit value in thousandths (millijoules for energy)
+    Observe32 = 0xF5,
+
     /// Local Rewind: REVERT [steps]
     Revert = 0xF3,
 
     /// Create new universe: BRANCH [energy] [dest_addr_for_id]
+    /// The child's id is stored in one byte; BRANCH.W stores it as 16-bit little-endian
+    /// A refused branch (low energy, or an id too wide for the field) stores 0
     Branch = 0xF4,
 
-    /// Allocate memory: MEM_ALLOC [v_addr] [size]
+    /// Signal an indirect target: SIGNAL @[target_addr] [len] [data...]
+    SignalTo = 0xF6,
+
+    /// Fully indirect signal: SIGNALI [target_addr] [buf_addr] [len_addr]
+    SignalInd = 0xF7,
+
+    /// Entangle an indirect target: ENTANGLE @[target_addr] [strength]
+    EntangleInd = 0xF8,
+
+    /// Observe an indirect target: OBSERVE @[target_addr] [metadata_type] [dest_addr]
+    ObserveInd = 0xF9,
+
+    /// Branch with energy read from memory: BRANCH @[energy_addr] [dest_addr_for_id]
+    /// The child's id is stored as a 16-bit little-endian value
+    BranchInd = 0xFA,
+
+    /// Allocate memory: MEM_ALLOC [v_addr] [pages] - first page id in r0
     MemAlloc = 0xA0,
 
-    /// Map memory (Entanglement): MEM_MAP [v_addr] [p_id]
+    /// Map memory (Entanglement): MEM_MAP [v_addr] [p_id_reg]
     MemMap = 0xA1,
 
     /// Swap to ground state: MEM_SWAP [v_addr]
     MemSwap = 0xA2,
+
+    /// Let another universe map a page: MEM_SHARE [v_addr] [universe_id_reg]
+    MemShare = 0xA3,
+
+    /// Stop the program with an exit code: EXIT [code]
+    Exit = 0xFC,
+
+    /// Wide-addressing prefix (v2): WIDE [opcode] [operands...]
+    /// Address operands of the prefixed instruction are 16-bit little-endian
+    Wide = 0xFE,
     
-    /// Terminate/Collapse
+    /// Stop the program (exit code 0)
     Halt = 0xFF,
 }
 
+/// Faults raised by the processor while executing an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessorFault {
+    /// Byte at the instruction pointer is not an opcode (or a doubled WIDE prefix)
+    InvalidOpcode,
+    /// Register operand outside r0-r7
+    InvalidRegister,
+    /// Operands run past the end of the program
+    TruncatedOperand,
+    /// Memory access or jump target outside the state vector
+    OutOfBounds,
+    /// PUSH/CALL with no room left on the stack
+    StackOverflow,
+    /// POP/RET on an empty stack
+    StackUnderflow,
+    /// DIV or MOD with a zero divisor
+    DivideByZero,
+    /// Access to an unmapped or unshared page, a mapping over the state vector,
+    /// or an empty allocation
+    PageFault,
+    /// HANDLER for an unknown event type, or IRET outside a handler
+    InvalidHandler,
+    /// Narrow CALL whose return address does not fit in a byte (use CALL.W)
+    ReturnOutOfRange,
+    /// Universe id too large for the field it is written to
+    IdOutOfRange,
+}
+
+impl ProcessorFault {
+    /// Numeric fault code, as written to a trap handler's registers
+    pub fn code(self) -> u8 {
+        match self {
+            ProcessorFault::InvalidOpcode => 1,
+            ProcessorFault::InvalidRegister => 2,
+            ProcessorFault::TruncatedOperand => 3,
+            ProcessorFault::OutOfBounds => 4,
+            ProcessorFault::StackOverflow => 5,
+            ProcessorFault::StackUnderflow => 6,
+            ProcessorFault::DivideByZero => 7,
+            ProcessorFault::PageFault => 8,
+            ProcessorFault::InvalidHandler => 9,
+            ProcessorFault::ReturnOutOfRange => 10,
+            ProcessorFault::IdOutOfRange => 11,
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessorFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessorFault::InvalidOpcode => write!(f, "invalid opcode"),
+            ProcessorFault::InvalidRegister => write!(f, "invalid register"),
+            ProcessorFault::TruncatedOperand => write!(f, "truncated operand"),
+            ProcessorFault::OutOfBounds => write!(f, "out-of-bounds access"),
+            ProcessorFault::StackOverflow => write!(f, "stack overflow"),
+            ProcessorFault::StackUnderflow => write!(f, "stack underflow"),
+            ProcessorFault::DivideByZero => write!(f, "divide by zero"),
+            ProcessorFault::PageFault => write!(f, "page fault"),
+            ProcessorFault::InvalidHandler => write!(f, "invalid event handler"),
+            ProcessorFault::ReturnOutOfRange => write!(f, "return address beyond 255 for narrow CALL"),
+            ProcessorFault::IdOutOfRange => write!(f, "universe id does not fit its field"),
+        }
+    }
+}
+
+/// Record of the last fault raised by a universe's program
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FaultRecord {
+    /// Fault kind
+    pub fault: ProcessorFault,
+    /// Instruction pointer of the faulting instruction
+    pub ip: usize,
+    /// Opcode byte of the faulting instruction (after any WIDE prefix)
+    pub opcode: u8,
+    /// The instruction carries a WIDE prefix
+    pub wide: bool,
+    /// Kernel evolution step at which the fault occurred
+    pub step: u64,
+}
+
+/// Register receiving the fault code when a fault traps into a handler
+pub const TRAP_CODE_REGISTER: usize = 7;
+
+/// Register receiving the faulting instruction pointer when a fault traps into a handler
+pub const TRAP_IP_REGISTER: usize = 6;
+
+/// Scale of fixed-point values written by SELF and OBSERVE32 (thousandths)
+pub const FIXED_POINT_SCALE: f64 = 1000.0;
+
+/// Property of its own universe a program reads with SELF/SELFR
+///
+/// Energy, entropy and stability are fixed-point (`FIXED_POINT_SCALE`);
+/// the rest are plain integers. Values saturate at `u32::MAX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfField {
+    /// Available energy
+    Energy,
+    /// Entropy
+    Entropy,
+    /// Stability score
+    Stability,
+    /// Universe id
+    Id,
+    /// Local timeline index
+    Timeline,
+    /// Instructions granted in the current time slice
+    Budget,
+    /// Events waiting in the inbox
+    InboxDepth,
+}
+
+impl SelfField {
+    /// Numeric field code (0-2 match OBSERVE's metadata types)
+    pub fn code(self) -> u8 {
+        match self {
+            SelfField::Energy => 0,
+            SelfField::Entropy => 1,
+            SelfField::Stability => 2,
+            SelfField::Id => 3,
+            SelfField::Timeline => 4,
+            SelfField::Budget => 5,
+            SelfField::InboxDepth => 6,
+        }
+    }
+
+    /// Field for a numeric code
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(SelfField::Energy),
+            1 => Some(SelfField::Entropy),
+            2 => Some(SelfField::Stability),
+            3 => Some(SelfField::Id),
+            4 => Some(SelfField::Timeline),
+            5 => Some(SelfField::Budget),
+            6 => Some(SelfField::InboxDepth),
+            _ => None,
+        }
+    }
+}
+
+/// Operand encodings of the Universal ISA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// State address or code target (1 byte, 2 bytes under WIDE)
+    Addr,
+    /// 8-bit literal (universe id, strength, length...)
+    Byte,
+    /// Address-sized literal (1 byte, 2 bytes under WIDE)
+    Word,
+    /// Register index (r0-r7)
+    Reg,
+    /// 16-bit little-endian literal
+    Imm16,
+    /// 32-bit little-endian literal
+    Imm32,
+    /// Length-prefixed inline data: [len] [data...]
+    Payload,
+}
+
 impl OpCode {
     /// Create OpCode from byte
     pub fn from_u8(v: u8) -> Option<Self> {
@@ -91,24 +466,333 @@ impl OpCode {
             0x04 => Some(OpCode::Add),
             0x05 => Some(OpCode::Sub),
             0x06 => Some(OpCode::Cmp),
+            0x07 => Some(OpCode::AddImm),
+            0x08 => Some(OpCode::SubImm),
+            0x09 => Some(OpCode::CmpImm),
             0x10 => Some(OpCode::Jump),
             0x11 => Some(OpCode::JumpIf),
             0x20 => Some(OpCode::Call),
             0x21 => Some(OpCode::Ret),
             0x22 => Some(OpCode::Push),
             0x23 => Some(OpCode::Pop),
+            0x30 => Some(OpCode::RegSet),
+            0x31 => Some(OpCode::RegLoad),
+            0x32 => Some(OpCode::RegStore),
+            0x33 => Some(OpCode::RegAdd),
+            0x34 => Some(OpCode::RegSub),
+            0x35 => Some(OpCode::RegCmp),
+            0x36 => Some(OpCode::RegAddImm),
+            0x37 => Some(OpCode::RegSubImm),
+            0x38 => Some(OpCode::RegCmpImm),
+            0x39 => Some(OpCode::Load),
+            0x3A => Some(OpCode::Store),
+            0x3B => Some(OpCode::LoadIdx),
+            0x3C => Some(OpCode::StoreIdx),
+            0x3D => Some(OpCode::LoadPtr),
+            0x3E => Some(OpCode::StorePtr),
+            0x60 => Some(OpCode::Mul),
+            0x61 => Some(OpCode::Div),
+            0x62 => Some(OpCode::Mod),
+            0x63 => Some(OpCode::And),
+            0x64 => Some(OpCode::Or),
+            0x65 => Some(OpCode::Shl),
+            0x66 => Some(OpCode::Shr),
+            0x67 => Some(OpCode::Not),
+            0x68 => Some(OpCode::CmpS),
+            0x40 => Some(OpCode::Set16),
+            0x41 => Some(OpCode::Add16),
+            0x42 => Some(OpCode::Sub16),
+            0x43 => Some(OpCode::Mul16),
+            0x44 => Some(OpCode::Div16),
+            0x45 => Some(OpCode::Cmp16),
+            0x46 => Some(OpCode::CmpS16),
+            0x47 => Some(OpCode::RegLoad16),
+            0x48 => Some(OpCode::RegStore16),
+            0x50 => Some(OpCode::Set32),
+            0x51 => Some(OpCode::Add32),
+            0x52 => Some(OpCode::Sub32),
+            0x53 => Some(OpCode::Mul32),
+            0x54 => Some(OpCode::Div32),
+            0x55 => Some(OpCode::Cmp32),
+            0x56 => Some(OpCode::CmpS32),
+            0x57 => Some(OpCode::RegLoad32),
+            0x58 => Some(OpCode::RegStore32),
+            0xE0 => Some(OpCode::Poll),
+            0xE1 => Some(OpCode::Recv),
+            0xE2 => Some(OpCode::Wait),
+            0xE3 => Some(OpCode::Handler),
+            0xE4 => Some(OpCode::Iret),
+            0xE8 => Some(OpCode::SelfInfo),
+            0xE9 => Some(OpCode::SelfInfoReg),
             0xF0 => Some(OpCode::Signal),
             0xF1 => Some(OpCode::Entangle),
             0xF2 => Some(OpCode::Observe),
+            0xF5 => Some(OpCode::Observe32),
+            0xF6 => Some(OpCode::SignalTo),
+            0xF7 => Some(OpCode::SignalInd),
+            0xF8 => Some(OpCode::EntangleInd),
+            0xF9 => Some(OpCode::ObserveInd),
+            0xFA => Some(OpCode::BranchInd),
             0xF3 => Some(OpCode::Revert),
             0xF4 => Some(OpCode::Branch),
             0xA0 => Some(OpCode::MemAlloc),
             0xA1 => Some(OpCode::MemMap),
             0xA2 => Some(OpCode::MemSwap),
+            0xA3 => Some(OpCode::MemShare),
+            0xFC => Some(OpCode::Exit),
+            0xFE => Some(OpCode::Wide),
             0xFF => Some(OpCode::Halt),
             _ => None,
         }
     }
+
+    /// Assembly mnemonic
+    ///
+    /// Indirect forms share the mnemonic of their direct form; the assembler
+    /// selects them with an `@` operand.
+    pub fn mnemonic(self) -> &'static str {
+        use OpCode::*;
+        match self {
+            NoOp => "NOP",
+            AtomSet => "SET",
+            AtomXor => "XOR",
+            AtomCopy => "COPY",
+            Add => "ADD",
+            Sub => "SUB",
+            Cmp => "CMP",
+            AddImm => "ADDI",
+            SubImm => "SUBI",
+            CmpImm => "CMPI",
+            Jump => "JUMP",
+            JumpIf => "JUMPIF",
+            Call => "CALL",
+            Ret => "RET",
+            Push => "PUSH",
+            Pop => "POP",
+            RegSet => "RSET",
+            RegLoad => "RLOAD",
+            RegStore => "RSTORE",
+            RegAdd => "RADD",
+            RegSub => "RSUB",
+            RegCmp => "RCMP",
+            RegAddImm => "RADDI",
+            RegSubImm => "RSUBI",
+            RegCmpImm => "RCMPI",
+            Load => "LOAD",
+            Store => "STORE",
+            LoadIdx => "LOADX",
+            StoreIdx => "STOREX",
+            LoadPtr => "LDP",
+            StorePtr => "STP",
+            Mul => "MUL",
+            Div => "DIV",
+            Mod => "MOD",
+            And => "AND",
+            Or => "OR",
+            Shl => "SHL",
+            Shr => "SHR",
+            Not => "NOT",
+            CmpS => "CMPS",
+            Set16 => "SET16",
+            Add16 => "ADD16",
+            Sub16 => "SUB16",
+            Mul16 => "MUL16",
+            Div16 => "DIV16",
+            Cmp16 => "CMP16",
+            CmpS16 => "CMPS16",
+            RegLoad16 => "RLOAD16",
+            RegStore16 => "RSTORE16",
+            Set32 => "SET32",
+            Add32 => "ADD32",
+            Sub32 => "SUB32",
+            Mul32 => "MUL32",
+            Div32 => "DIV32",
+            Cmp32 => "CMP32",
+            CmpS32 => "CMPS32",
+            RegLoad32 => "RLOAD32",
+            RegStore32 => "RSTORE32",
+            Poll => "POLL",
+            Recv => "RECV",
+            Wait => "WAIT",
+            Handler => "HANDLER",
+            Iret => "IRET",
+            SelfInfo => "SELF",
+            SelfInfoReg => "SELFR",
+            Signal | SignalTo => "SIGNAL",
+            SignalInd => "SIGNALI",
+            Entangle | EntangleInd => "ENTANGLE",
+            Observe | ObserveInd => "OBSERVE",
+            Observe32 => "OBSERVE32",
+            Revert => "REVERT",
+            Branch | BranchInd => "BRANCH",
+            MemAlloc => "MEMALLOC",
+            MemMap => "MEMMAP",
+            MemSwap => "MEMSWAP",
+            MemShare => "MEMSHARE",
+            Exit => "EXIT",
+            Wide => "WIDE",
+            Halt => "HALT",
+        }
+    }
+
+    /// Check if this is the `@addr` form of another opcode
+    pub fn is_indirect(self) -> bool {
+        matches!(self, OpCode::SignalTo | OpCode::EntangleInd | OpCode::ObserveInd | OpCode::BranchInd)
+    }
+
+    /// Operand layout of this opcode, in encoding order
+    pub fn operands(self) -> &'static [Operand] {
+        use Operand::*;
+        match self {
+            OpCode::NoOp | OpCode::Ret | OpCode::Iret | OpCode::Wide | OpCode::Halt => &[],
+            OpCode::AtomSet | OpCode::AtomXor => &[Addr, Byte],
+            OpCode::AtomCopy => &[Addr, Addr, Byte],
+            OpCode::Add | OpCode::Sub | OpCode::JumpIf => &[Addr, Addr],
+            OpCode::Cmp => &[Addr, Addr, Addr],
+            OpCode::AddImm | OpCode::SubImm => &[Addr, Byte],
+            OpCode::CmpImm => &[Addr, Byte, Addr],
+            OpCode::RegSet | OpCode::RegAddImm | OpCode::RegSubImm => &[Reg, Word],
+            OpCode::RegLoad => &[Reg, Addr],
+            OpCode::RegStore => &[Addr, Reg],
+            OpCode::RegAdd | OpCode::RegSub => &[Reg, Reg],
+            OpCode::RegCmp => &[Reg, Reg, Addr],
+            OpCode::RegCmpImm => &[Reg, Word, Addr],
+            OpCode::Load => &[Reg, Reg, Word],
+            OpCode::Store => &[Reg, Word, Reg],
+            OpCode::LoadIdx => &[Reg, Addr, Reg],
+            OpCode::StoreIdx => &[Addr, Reg, Reg],
+            OpCode::LoadPtr | OpCode::StorePtr => &[Addr, Addr],
+            OpCode::Mul | OpCode::Div | OpCode::Mod | OpCode::And | OpCode::Or => &[Addr, Addr],
+            OpCode::Shl | OpCode::Shr => &[Addr, Byte],
+            OpCode::Not => &[Addr],
+            OpCode::CmpS => &[Addr, Addr, Addr],
+            OpCode::Set16 => &[Addr, Imm16],
+            OpCode::Set32 => &[Addr, Imm32],
+            OpCode::Add16 | OpCode::Sub16 | OpCode::Mul16 | OpCode::Div16
+            | OpCode::Add32 | OpCode::Sub32 | OpCode::Mul32 | OpCode::Div32 => &[Addr, Addr],
+            OpCode::Cmp16 | OpCode::CmpS16 | OpCode::Cmp32 | OpCode::CmpS32 => &[Addr, Addr, Addr],
+            OpCode::RegLoad16 | OpCode::RegLoad32 => &[Reg, Addr],
+            OpCode::RegStore16 | OpCode::RegStore32 => &[Addr, Reg],
+            OpCode::Jump | OpCode::Call | OpCode::Push | OpCode::Pop => &[Addr],
+            OpCode::Signal => &[Byte, Payload],
+            OpCode::Entangle => &[Byte, Byte],
+            OpCode::Observe | OpCode::Observe32 => &[Byte, Byte, Addr],
+            OpCode::Revert | OpCode::Exit | OpCode::Wait => &[Byte],
+            OpCode::Poll => &[Addr],
+            OpCode::Recv => &[Addr, Byte],
+            OpCode::Handler | OpCode::SelfInfo => &[Byte, Addr],
+            OpCode::SelfInfoReg => &[Reg, Byte],
+            OpCode::Branch => &[Byte, Addr],
+            OpCode::SignalTo => &[Addr, Payload],
+            OpCode::SignalInd => &[Addr, Addr, Addr],
+            OpCode::EntangleInd => &[Addr, Byte],
+            OpCode::ObserveInd => &[Addr, Byte, Addr],
+            OpCode::BranchInd => &[Addr, Addr],
+            OpCode::MemAlloc => &[Addr, Byte],
+            OpCode::MemMap => &[Addr, Reg],
+            OpCode::MemSwap => &[Addr],
+            OpCode::MemShare => &[Addr, Reg],
+        }
+    }
+
+    /// Encoded length of an instruction with this opcode (including any WIDE prefix)
+    pub fn encoded_len(self, wide: bool, payload_len: usize) -> usize {
+        let prefix = if wide { 2 } else { 1 };
+        prefix + self.operands().iter().map(|operand| match operand {
+            Operand::Addr | Operand::Word => if wide { 2 } else { 1 },
+            Operand::Byte | Operand::Reg => 1,
+            Operand::Imm16 => 2,
+            Operand::Imm32 => 4,
+            Operand::Payload => 1 + payload_len,
+        }).sum::<usize>()
+    }
+
+    /// Width in bytes of the integers this opcode operates on
+    pub fn int_width(self) -> usize {
+        match self as u8 {
+            0x40..=0x4F => 2,
+            0x50..=0x5F => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// A decoded instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    /// Operation to perform
+    pub opcode: OpCode,
+    /// Decoded under the WIDE prefix
+    pub wide: bool,
+    /// Operand values in encoding order (a payload operand holds its length)
+    pub args: [usize; 4],
+    /// Location of the inline payload within the program, if any
+    pub payload: Option<Range<usize>>,
+    /// Encoded length in bytes (including any prefix)
+    pub len: usize,
+}
+
+impl Instruction {
+    /// Decode the instruction starting at `ip`
+    ///
+    /// Fails with the fault the processor raises for unknown opcodes, truncated
+    /// operands and invalid registers.
+    pub fn decode(code: &[u8], ip: usize) -> std::result::Result<Instruction, ProcessorFault> {
+        use ProcessorFault::{InvalidOpcode, TruncatedOperand};
+
+        let byte_at = |pos: usize| code.get(pos).copied().ok_or(TruncatedOperand);
+        let bytes_at = |pos: usize, len: usize| code.get(pos..pos + len).ok_or(TruncatedOperand);
+
+        let mut pos = ip;
+        let mut opcode = OpCode::from_u8(byte_at(pos)?).ok_or(InvalidOpcode)?;
+        let wide = opcode == OpCode::Wide;
+        if wide {
+            pos += 1;
+            opcode = OpCode::from_u8(byte_at(pos)?).ok_or(InvalidOpcode)?;
+            if opcode == OpCode::Wide {
+                return Err(InvalidOpcode);
+            }
+        }
+        pos += 1;
+
+        let mut args = [0usize; 4];
+        let mut payload = None;
+        for (i, operand) in opcode.operands().iter().enumerate() {
+            match operand {
+                Operand::Addr | Operand::Word if wide => {
+                    args[i] = read_le(bytes_at(pos, 2)?) as usize;
+                    pos += 2;
+                }
+                Operand::Reg => {
+                    let reg = byte_at(pos)? as usize;
+                    if reg >= REGISTER_COUNT {
+                        return Err(ProcessorFault::InvalidRegister);
+                    }
+                    args[i] = reg;
+                    pos += 1;
+                }
+                Operand::Addr | Operand::Word | Operand::Byte => {
+                    args[i] = byte_at(pos)? as usize;
+                    pos += 1;
+                }
+                Operand::Imm16 | Operand::Imm32 => {
+                    let width = if *operand == Operand::Imm16 { 2 } else { 4 };
+                    args[i] = read_le(bytes_at(pos, width)?) as usize;
+                    pos += width;
+                }
+                Operand::Payload => {
+                    let len = byte_at(pos)? as usize;
+                    pos += 1;
+                    bytes_at(pos, len)?;
+                    args[i] = len;
+                    payload = Some(pos..pos + len);
+                    pos += len;
+                }
+            }
+        }
+
+        Ok(Instruction { opcode, wide, args, payload, len: pos - ip })
+    }
 }
 
 /// The Universal Processor
@@ -117,351 +801,1233 @@ impl OpCode {
 #[derive(Debug)]
 pub struct UniversalProcessor;
 
+/// Bytes reachable by narrow (8-bit) address operands
+pub const NARROW_ADDRESS_SPACE: usize = 256;
+
+/// Default call stack depth in bytes
+pub const DEFAULT_STACK_DEPTH: usize = 256;
+
+/// Per-universe call and data stack
+///
+/// Lives outside the state vector, so program code and data can never be
+/// overwritten by CALL/PUSH. Return addresses are kept apart from PUSH/POP
+/// data, so a callee can pop the arguments its caller pushed before the
+/// CALL. Both share the depth: pushing past it raises `StackOverflow`, and
+/// popping an empty stack raises `StackUnderflow`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallStack {
+    bytes: Vec<u8>,
+    /// Return address bytes pushed by CALL
+    #[serde(default)]
+    returns: Vec<u8>,
+    depth: usize,
+}
+
+impl Default for CallStack {
+    fn default() -> Self {
+        Self::new(DEFAULT_STACK_DEPTH)
+    }
+}
+
+impl CallStack {
+    /// Create an empty stack holding at most `depth` bytes
+    pub fn new(depth: usize) -> Self {
+        Self { bytes: Vec::new(), returns: Vec::new(), depth }
+    }
+
+    /// Push a byte
+    pub fn push(&mut self, value: u8) -> std::result::Result<(), ProcessorFault> {
+        if self.len() >= self.depth {
+            return Err(ProcessorFault::StackOverflow);
+        }
+        self.bytes.push(value);
+        Ok(())
+    }
+
+    /// Pop the most recently pushed byte
+    pub fn pop(&mut self) -> std::result::Result<u8, ProcessorFault> {
+        self.bytes.pop().ok_or(ProcessorFault::StackUnderflow)
+    }
+
+    /// Push a return address's bytes in order, or none if they do not all fit
+    pub fn push_return(&mut self, values: &[u8]) -> std::result::Result<(), ProcessorFault> {
+        if self.len() + values.len() > self.depth {
+            return Err(ProcessorFault::StackOverflow);
+        }
+        self.returns.extend_from_slice(values);
+        Ok(())
+    }
+
+    /// Pop `N` return address bytes, most recently pushed first, or none if fewer are on the stack
+    pub fn pop_return<const N: usize>(&mut self) -> std::result::Result<[u8; N], ProcessorFault> {
+        let start = self.returns.len().checked_sub(N).ok_or(ProcessorFault::StackUnderflow)?;
+        let mut values = [0; N];
+        for (value, byte) in values.iter_mut().zip(self.returns.drain(start..).rev()) {
+            *value = byte;
+        }
+        Ok(values)
+    }
+
+    /// Bytes currently on the stack, data and return addresses together
+    pub fn len(&self) -> usize {
+        self.bytes.len() + self.returns.len()
+    }
+
+    /// Check if the stack is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of bytes the stack holds
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// PUSH/POP data, bottom first
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Return address bytes, bottom first
+    pub fn returns(&self) -> &[u8] {
+        &self.returns
+    }
+}
+
+/// Size of the message header written by RECV: source (u16 LE), type, length
+pub const RECV_HEADER_LEN: usize = 4;
+
+/// Size of a universe id stored in the state vector (u16 LE); narrow BRANCH stores one byte
+pub const UNIVERSE_ID_LEN: usize = 2;
+
+/// Read a universe id stored in the state vector (as written by BRANCH and RECV)
+fn read_universe_id(state: &[u8], addr: usize) -> std::result::Result<u64, ProcessorFault> {
+    read_int(state, addr, UNIVERSE_ID_LEN).map(u64::from)
+}
+
+/// Encode a state address for an event payload (16-bit little-endian)
+fn addr_bytes(addr: usize) -> [u8; 2] {
+    (addr as u16).to_le_bytes()
+}
+
+/// Read a little-endian integer of up to 4 bytes
+fn read_le(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+/// Read a `width`-byte little-endian integer from the state vector
+fn read_int(state: &[u8], addr: usize, width: usize) -> std::result::Result<u32, ProcessorFault> {
+    state.get(addr..addr + width).map(read_le).ok_or(ProcessorFault::OutOfBounds)
+}
+
+/// Write the low `width` bytes of `value` little-endian; returns true if the state changed
+fn write_int(state: &mut [u8], addr: usize, width: usize, value: u32) -> std::result::Result<bool, ProcessorFault> {
+    let bytes = &value.to_le_bytes()[..width];
+    let slot = state.get_mut(addr..addr + width).ok_or(ProcessorFault::OutOfBounds)?;
+    let changed = *slot != *bytes;
+    slot.copy_from_slice(bytes);
+    Ok(changed)
+}
+
+/// Pointer width in the state vector (u16 LE)
+pub const POINTER_LEN: usize = 2;
+
+/// Read a byte at a computed address: the state vector first, then mapped pages
+///
+/// Returns the byte and the access mass of the page it came from.
+fn load_byte(
+    state: &[u8],
+    memory: &mut MultiversalMemory,
+    pool: &mut PagePool,
+    addr: usize,
+) -> std::result::Result<(u8, f64), ProcessorFault> {
+    match state.get(addr) {
+        Some(&value) => Ok((value, 0.0)),
+        None => memory.read_byte(pool, addr).ok_or(ProcessorFault::PageFault),
+    }
+}
+
+/// Write a byte at a computed address
+///
+/// Returns whether the byte changed and the energy of the page access.
+fn store_byte(
+    state: &mut [u8],
+    memory: &mut MultiversalMemory,
+    pool: &mut PagePool,
+    addr: usize,
+    value: u8,
+) -> std::result::Result<(bool, f64), ProcessorFault> {
+    match state.get_mut(addr) {
+        Some(slot) => {
+            let changed = *slot != value;
+            *slot = value;
+            Ok((changed, 0.0))
+        }
+        None => memory.write_byte(pool, addr, value).ok_or(ProcessorFault::PageFault),
+    }
+}
+
+/// Virtual page index for a mapping at `v_addr`; pages may not overlap the state vector
+fn mappable_page(state: &[u8], memory: &MultiversalMemory, v_addr: usize) -> std::result::Result<usize, ProcessorFault> {
+    let page = v_addr / memory.page_size;
+    if page * memory.page_size < state.len() {
+        return Err(ProcessorFault::PageFault);
+    }
+    Ok(page)
+}
+
+/// Fail with `OutOfBounds` unless `addr..addr + len` lies within the state vector
+fn check_bounds(state: &[u8], addr: usize, len: usize) -> std::result::Result<(), ProcessorFault> {
+    if addr + len <= state.len() {
+        Ok(())
+    } else {
+        Err(ProcessorFault::OutOfBounds)
+    }
+}
+
+/// Sign-extend a `width`-byte integer
+fn sign_extend(value: u32, width: usize) -> i32 {
+    match width {
+        1 => value as u8 as i8 as i32,
+        2 => value as u16 as i16 as i32,
+        _ => value as i32,
+    }
+}
+
+/// Three-way comparison result as stored by CMP (1 = greater, 0 = equal, 255 = less)
+fn compare<T: Ord>(a: T, b: T) -> u8 {
+    match a.cmp(&b) {
+        std::cmp::Ordering::Greater => 1,
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Less => 255,
+    }
+}
+
 impl UniversalProcessor {
     /// Execute a single instruction cycle
     ///
-    /// # Arguments
-    /// * `state`: The universe's memory (code + data)
-    /// * `ip`: Instruction Pointer (offset in state)
-    /// * `energy_budget`: Available energy
+    /// Runs the instruction at the universe's instruction pointer against its
+    /// state vector (code + data), register file and memory system. Mapped
+    /// pages live in the kernel's page pool; energy is charged per `costs`.
     ///
     /// # Returns
     /// * `(New IP, Energy Cost, OutputEvent)`
     pub fn step(
-        state: &mut Vec<u8>,
-        ip: usize,
-        memory_sys: &mut super::memory::MultiversalMemory,
+        universe: &mut Universe,
+        pool: &mut PagePool,
+        costs: &EnergyCostModel,
     ) -> Result<(usize, f64, Option<crate::interaction::CausalEvent>)> {
+        let ip = universe.instruction_pointer;
+        Self::execute(universe, pool, costs).map_err(|fault| KernelError::ProcessorFault { fault, ip })
+    }
+
+    /// Instruction semantics; any fault aborts the instruction without advancing IP
+    fn execute(
+        universe: &mut Universe,
+        pool: &mut PagePool,
+        costs: &EnergyCostModel,
+    ) -> std::result::Result<(usize, f64, Option<crate::interaction::CausalEvent>), ProcessorFault> {
+        let ip = universe.instruction_pointer;
+        let universe_id = universe.id;
+        let state = universe.state_vector.data.as_mut_slice();
+        let registers = &mut universe.registers;
+        let memory_sys = &mut universe.memory;
+        let execution_state = &mut universe.execution_state;
+        let inbox = &mut universe.inbox;
+        let stack = &mut universe.stack;
+        let handlers = &mut universe.handlers;
+        let handler_return = &mut universe.handler_return;
+
         if ip >= state.len() {
-            return Ok((0, 0.0, None)); // Wrap around or halt
+            return Ok((0, 0.0, None)); // Ran off the end: wrap around
         }
 
-        let op = OpCode::from_u8(state[ip]).unwrap_or(OpCode::NoOp);
-        let mut cost = 0.0001; // Base thermodynamic cost
-        let mut next_ip = ip + 1;
+        let mut cost = costs.cycle; // Base thermodynamic cost
+
+        let insn = Instruction::decode(state, ip)?;
+        let args = insn.args;
+        let op_cost = costs.op(insn.opcode);
+        let mut next_ip = ip + insn.len;
         let mut event = None;
 
-        match op {
-            OpCode::NoOp => {
+        match insn.opcode {
+            OpCode::NoOp | OpCode::Wide => {
                 // Just burn entropy
+                cost += op_cost.charge(0);
             }
             OpCode::AtomSet => {
-                if ip + 2 < state.len() {
-                    let addr = state[ip+1] as usize; // Simplified 8-bit addressing for demo
-                    let val = state[ip+2];
-                    
-                    // LAW 1: Bit erasure costs kTint2 (simplification)
-                    if state.len() > addr {
-                        // If changing value, cost is higher
-                        if state[addr] != val {
-                            cost += 0.01;
-                        }
-                        state[addr] = val;
-                    }
-                    next_ip += 2;
+                let addr = args[0];
+                let val = args[1] as u8;
+                check_bounds(state, addr, 1)?;
+
+                // LAW 1: Bit erasure costs kTint2 (simplification)
+                // If changing value, cost is higher
+                if state[addr] != val {
+                    cost += costs.erasure(1);
                 }
+                state[addr] = val;
+                cost += op_cost.charge(0);
             }
             OpCode::AtomXor => {
-                if ip + 2 < state.len() {
-                    let addr = state[ip+1] as usize;
-                    let val = state[ip+2];
-                    if state.len() > addr {
-                        state[addr] ^= val;
-                        cost += 0.005; // Reversible is cheaper
-                    }
-                    next_ip += 2;
-                }
+                let addr = args[0];
+                check_bounds(state, addr, 1)?;
+                state[addr] ^= args[1] as u8;
+                cost += op_cost.charge(0);
             }
             OpCode::AtomCopy => {
-                 if ip + 3 < state.len() {
-                    let src = state[ip+1] as usize;
-                    let dest = state[ip+2] as usize;
-                    let len = state[ip+3] as usize;
-                    
-                    if src + len <= state.len() && dest + len <= state.len() {
-                        let slice = state[src..src+len].to_vec();
-                        for (i, b) in slice.iter().enumerate() {
-                            state[dest + i] = *b;
-                        }
-                        cost += 0.001 * len as f64;
-                    }
-                    next_ip += 3;
-                 }
-            }
-            OpCode::Add => {
-                // ADD [dest] [src] - dest = dest + src
-                if ip + 2 < state.len() {
-                    let dest = state[ip+1] as usize;
-                    let src = state[ip+2] as usize;
-                    if dest < state.len() && src < state.len() {
-                        state[dest] = state[dest].wrapping_add(state[src]);
-                        cost += 0.002;
-                    }
-                    next_ip += 2;
+                let (src, dest, len) = (args[0], args[1], args[2]);
+                check_bounds(state, src, len)?;
+                check_bounds(state, dest, len)?;
+                state.copy_within(src..src + len, dest);
+                cost += op_cost.charge(len);
+            }
+            OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div | OpCode::Mod | OpCode::And | OpCode::Or => {
+                // ALU [dest] [src] - dest = dest <op> src
+                let (dest, src) = (args[0], args[1]);
+                check_bounds(state, dest, 1)?;
+                check_bounds(state, src, 1)?;
+                let (a, b) = (state[dest], state[src]);
+                state[dest] = match insn.opcode {
+                    OpCode::Add => a.wrapping_add(b),
+                    OpCode::Sub => a.wrapping_sub(b),
+                    OpCode::Mul => a.wrapping_mul(b),
+                    OpCode::Div => a.checked_div(b).ok_or(ProcessorFault::DivideByZero)?,
+                    OpCode::Mod => a.checked_rem(b).ok_or(ProcessorFault::DivideByZero)?,
+                    OpCode::And => a & b,
+                    _ => a | b,
+                };
+                cost += op_cost.charge(0);
+            }
+            OpCode::Cmp | OpCode::CmpS => {
+                // CMP [a] [b] [result] - result = 1 if a > b, 0 if equal, 255 if a < b
+                // CMPS compares the bytes as i8
+                let (a_addr, b_addr, result_addr) = (args[0], args[1], args[2]);
+                check_bounds(state, a_addr, 1)?;
+                check_bounds(state, b_addr, 1)?;
+                check_bounds(state, result_addr, 1)?;
+                let (a, b) = (state[a_addr], state[b_addr]);
+                state[result_addr] = if insn.opcode == OpCode::CmpS {
+                    compare(a as i8, b as i8)
+                } else {
+                    compare(a, b)
+                };
+                cost += op_cost.charge(0);
+            }
+            OpCode::Shl | OpCode::Shr => {
+                // SHL/SHR [dest] [bits] - shifted-out bits are erased
+                let dest = args[0];
+                check_bounds(state, dest, 1)?;
+                let bits = args[1] as u32;
+                state[dest] = if insn.opcode == OpCode::Shl {
+                    state[dest].checked_shl(bits).unwrap_or(0)
+                } else {
+                    state[dest].checked_shr(bits).unwrap_or(0)
+                };
+                cost += op_cost.charge(0);
+            }
+            OpCode::Not => {
+                let dest = args[0];
+                check_bounds(state, dest, 1)?;
+                state[dest] = !state[dest];
+                cost += op_cost.charge(0);
+            }
+            OpCode::Set16 | OpCode::Set32 => {
+                let width = insn.opcode.int_width();
+                if write_int(state, args[0], width, args[1] as u32)? {
+                    cost += costs.erasure(width); // Bit erasure per byte (LAW 1)
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::Sub => {
-                // SUB [dest] [src] - dest = dest - src
-                if ip + 2 < state.len() {
-                    let dest = state[ip+1] as usize;
-                    let src = state[ip+2] as usize;
-                    if dest < state.len() && src < state.len() {
-                        state[dest] = state[dest].wrapping_sub(state[src]);
-                        cost += 0.002;
-                    }
-                    next_ip += 2;
+            OpCode::Add16 | OpCode::Sub16 | OpCode::Mul16 | OpCode::Div16
+            | OpCode::Add32 | OpCode::Sub32 | OpCode::Mul32 | OpCode::Div32 => {
+                // Multi-byte ALU [dest] [src], costs scale with the bytes touched
+                let width = insn.opcode.int_width();
+                let (dest, src) = (args[0], args[1]);
+                let (a, b) = (read_int(state, dest, width)?, read_int(state, src, width)?);
+                let result = match insn.opcode {
+                    OpCode::Add16 | OpCode::Add32 => a.wrapping_add(b),
+                    OpCode::Sub16 | OpCode::Sub32 => a.wrapping_sub(b),
+                    OpCode::Mul16 | OpCode::Mul32 => a.wrapping_mul(b),
+                    _ => a.checked_div(b).ok_or(ProcessorFault::DivideByZero)?,
+                };
+                write_int(state, dest, width, result)?;
+                cost += op_cost.charge(width);
+            }
+            OpCode::Cmp16 | OpCode::CmpS16 | OpCode::Cmp32 | OpCode::CmpS32 => {
+                let width = insn.opcode.int_width();
+                let result_addr = args[2];
+                let (a, b) = (read_int(state, args[0], width)?, read_int(state, args[1], width)?);
+                check_bounds(state, result_addr, 1)?;
+                state[result_addr] = match insn.opcode {
+                    OpCode::CmpS16 | OpCode::CmpS32 => compare(sign_extend(a, width), sign_extend(b, width)),
+                    _ => compare(a, b),
+                };
+                cost += op_cost.charge(width);
+            }
+            OpCode::RegLoad | OpCode::RegLoad16 | OpCode::RegLoad32 => {
+                let width = insn.opcode.int_width();
+                registers[args[0]] = read_int(state, args[1], width)?;
+                cost += op_cost.charge(width);
+            }
+            OpCode::RegStore | OpCode::RegStore16 | OpCode::RegStore32 => {
+                // Writing back to the state vector is a bit erasure (LAW 1)
+                let width = insn.opcode.int_width();
+                if write_int(state, args[0], width, registers[args[1]])? {
+                    cost += costs.erasure(width);
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::Cmp => {
-                // CMP [a] [b] [result] - result = 1 if a > b, 0 if equal, 255 if a < b
-                if ip + 3 < state.len() {
-                    let a_addr = state[ip+1] as usize;
-                    let b_addr = state[ip+2] as usize;
-                    let result_addr = state[ip+3] as usize;
-                    
-                    if a_addr < state.len() && b_addr < state.len() && result_addr < state.len() {
-                        let a = state[a_addr];
-                        let b = state[b_addr];
-                        state[result_addr] = if a > b { 1 } else if a == b { 0 } else { 255 };
-                        cost += 0.001;
+            OpCode::Load | OpCode::LoadIdx | OpCode::LoadPtr => {
+                // Computed-address loads reach mapped pages beyond the state vector
+                let addr = match insn.opcode {
+                    OpCode::Load => (registers[args[1]] as usize).wrapping_add(args[2]),
+                    OpCode::LoadIdx => args[1].wrapping_add(registers[args[2]] as usize),
+                    _ => read_int(state, args[1], POINTER_LEN)? as usize,
+                };
+                let (value, mass) = load_byte(state, memory_sys, pool, addr)?;
+                if insn.opcode == OpCode::LoadPtr {
+                    check_bounds(state, args[0], 1)?;
+                    state[args[0]] = value;
+                } else {
+                    registers[args[0]] = value as u32;
+                }
+                cost += op_cost.charge(0) + mass;
+            }
+            OpCode::Store | OpCode::StoreIdx | OpCode::StorePtr => {
+                let (addr, value) = match insn.opcode {
+                    OpCode::Store => ((registers[args[0]] as usize).wrapping_add(args[1]), registers[args[2]] as u8),
+                    OpCode::StoreIdx => (args[0].wrapping_add(registers[args[1]] as usize), registers[args[2]] as u8),
+                    _ => {
+                        check_bounds(state, args[1], 1)?;
+                        (read_int(state, args[0], POINTER_LEN)? as usize, state[args[1]])
                     }
-                    next_ip += 3;
+                };
+                let (changed, mass) = store_byte(state, memory_sys, pool, addr, value)?;
+                if changed {
+                    cost += costs.erasure(1); // Bit erasure (LAW 1)
                 }
+                cost += op_cost.charge(0) + mass;
+            }
+            OpCode::AddImm | OpCode::SubImm => {
+                // ADDI/SUBI [dest] [imm]
+                let dest = args[0];
+                check_bounds(state, dest, 1)?;
+                state[dest] = if insn.opcode == OpCode::AddImm {
+                    state[dest].wrapping_add(args[1] as u8)
+                } else {
+                    state[dest].wrapping_sub(args[1] as u8)
+                };
+                cost += op_cost.charge(0);
+            }
+            OpCode::CmpImm => {
+                // CMPI [a] [imm] [result]
+                let (a_addr, result_addr) = (args[0], args[2]);
+                check_bounds(state, a_addr, 1)?;
+                check_bounds(state, result_addr, 1)?;
+                state[result_addr] = compare(state[a_addr], args[1] as u8);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegSet => {
+                // Registers hold no stored potential, so register ops are cheap
+                registers[args[0]] = args[1] as u32;
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegAdd => {
+                registers[args[0]] = registers[args[0]].wrapping_add(registers[args[1]]);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegSub => {
+                registers[args[0]] = registers[args[0]].wrapping_sub(registers[args[1]]);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegCmp => {
+                let result_addr = args[2];
+                check_bounds(state, result_addr, 1)?;
+                state[result_addr] = compare(registers[args[0]], registers[args[1]]);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegAddImm => {
+                registers[args[0]] = registers[args[0]].wrapping_add(args[1] as u32);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegSubImm => {
+                registers[args[0]] = registers[args[0]].wrapping_sub(args[1] as u32);
+                cost += op_cost.charge(0);
+            }
+            OpCode::RegCmpImm => {
+                let result_addr = args[2];
+                check_bounds(state, result_addr, 1)?;
+                state[result_addr] = compare(registers[args[0]], args[1] as u32);
+                cost += op_cost.charge(0);
             }
             OpCode::Jump => {
                 // JUMP [addr] - Unconditional jump
-                if ip + 1 < state.len() {
-                    let target = state[ip+1] as usize;
-                    next_ip = target;
-                    cost += 0.0005;
-                }
+                check_bounds(state, args[0], 1)?;
+                next_ip = args[0];
+                cost += op_cost.charge(0);
             }
             OpCode::JumpIf => {
-                if ip + 2 < state.len() {
-                    let cond_addr = state[ip+1] as usize;
-                    let target = state[ip+2] as usize;
-                    
-                    if state.len() > cond_addr && state[cond_addr] != 0 {
-                        next_ip = target;
-                    } else {
-                        next_ip += 2;
-                    }
+                let (cond_addr, target) = (args[0], args[1]);
+                check_bounds(state, cond_addr, 1)?;
+                if state[cond_addr] != 0 {
+                    check_bounds(state, target, 1)?;
+                    next_ip = target;
                 }
+                cost += op_cost.charge(0);
             }
             OpCode::Call => {
-                // CALL [addr] - Push return address (IP+2) to stack, jump to addr
-                // Stack pointer is stored at address 255 (top of 8-bit address space)
-                if ip + 1 < state.len() {
-                    let target = state[ip+1] as usize;
-                    let sp_addr = 255usize;
-                    
-                    if sp_addr < state.len() {
-                        let sp = state[sp_addr] as usize;
-                        let return_addr = (ip + 2) as u8;
-                        
-                        // Push return address
-                        if sp > 0 && sp < state.len() {
-                            state[sp] = return_addr;
-                            state[sp_addr] = state[sp_addr].wrapping_sub(1); // Decrement SP
-                        }
-                        
-                        next_ip = target;
-                        cost += 0.003;
-                    }
+                // CALL [addr] - Push return address to the call stack, jump to addr
+                // Wide calls push a 16-bit return address (high byte first)
+                check_bounds(state, args[0], 1)?;
+                let return_addr = next_ip;
+                if insn.wide {
+                    let [lo, hi] = addr_bytes(return_addr);
+                    stack.push_return(&[hi, lo])?;
+                } else {
+                    let return_addr = u8::try_from(return_addr).map_err(|_| ProcessorFault::ReturnOutOfRange)?;
+                    stack.push_return(&[return_addr])?;
                 }
+                next_ip = args[0];
+                cost += op_cost.charge(0);
             }
             OpCode::Ret => {
                 // RET - Pop return address from stack, jump to it
-                let sp_addr = 255usize;
-                if sp_addr < state.len() {
-                    let sp = state[sp_addr].wrapping_add(1) as usize; // Increment SP first
-                    
-                    if sp < state.len() {
-                        state[sp_addr] = sp as u8;
-                        next_ip = state[sp] as usize;
-                        cost += 0.002;
-                    }
-                }
+                next_ip = if insn.wide {
+                    u16::from_le_bytes(stack.pop_return::<2>()?) as usize
+                } else {
+                    stack.pop_return::<1>()?[0] as usize
+                };
+                cost += op_cost.charge(0);
             }
             OpCode::Push => {
                 // PUSH [addr] - Push value at addr to stack
-                if ip + 1 < state.len() {
-                    let addr = state[ip+1] as usize;
-                    let sp_addr = 255usize;
-                    
-                    if addr < state.len() && sp_addr < state.len() {
-                        let sp = state[sp_addr] as usize;
-                        if sp > 0 && sp < state.len() {
-                            state[sp] = state[addr];
-                            state[sp_addr] = state[sp_addr].wrapping_sub(1);
-                            cost += 0.002;
-                        }
-                    }
-                    next_ip += 1;
-                }
+                let addr = args[0];
+                check_bounds(state, addr, 1)?;
+                stack.push(state[addr])?;
+                cost += op_cost.charge(0);
             }
             OpCode::Pop => {
                 // POP [addr] - Pop value from stack to addr
-                if ip + 1 < state.len() {
-                    let addr = state[ip+1] as usize;
-                    let sp_addr = 255usize;
-                    
-                    if addr < state.len() && sp_addr < state.len() {
-                        let sp = state[sp_addr].wrapping_add(1) as usize;
-                        if sp < state.len() {
-                            state[sp_addr] = sp as u8;
-                            state[addr] = state[sp];
-                            cost += 0.002;
-                        }
+                let addr = args[0];
+                check_bounds(state, addr, 1)?;
+                state[addr] = stack.pop()?;
+                cost += op_cost.charge(0);
+            }
+            OpCode::Poll => {
+                let dest = args[0];
+                check_bounds(state, dest, 1)?;
+                state[dest] = inbox.len().min(u8::MAX as usize) as u8;
+                cost += op_cost.charge(0);
+            }
+            OpCode::Recv => {
+                // RECV [buf] [max_len] - header + payload truncated to max_len
+                let (buf, max_len) = (args[0], args[1]);
+                check_bounds(state, buf, RECV_HEADER_LEN + max_len)?;
+                if let Some(message) = inbox.front() {
+                    u16::try_from(message.source.0).map_err(|_| ProcessorFault::IdOutOfRange)?;
+                }
+                match inbox.pop_front() {
+                    Some(message) => {
+                        let payload = message.data.expand();
+                        let len = payload.len().min(max_len);
+                        let [src_lo, src_hi] = (message.source.0 as u16).to_le_bytes();
+                        state[buf..buf + RECV_HEADER_LEN]
+                            .copy_from_slice(&[src_lo, src_hi, message.event_type.code(), len as u8]);
+                        state[buf + RECV_HEADER_LEN..buf + RECV_HEADER_LEN + len].copy_from_slice(&payload[..len]);
+                        cost += op_cost.charge(len);
+                    }
+                    None => {
+                        state[buf..buf + RECV_HEADER_LEN].fill(0);
+                        cost += costs.op(OpCode::Poll).charge(0); // Nothing received: priced like POLL
                     }
-                    next_ip += 1;
                 }
             }
-            OpCode::Signal => {
-                // SIGNAL [target_id] [len] [data...]
-                if ip + 3 < state.len() {
-                    let target_id = state[ip+1] as u64; // Simple addressing (0-255)
-                    let len = state[ip+2] as usize;
-                    
-                    if ip + 3 + len <= state.len() {
-                        let data = state[ip+3..ip+3+len].to_vec();
-                        
-                        // Create event to be sent
-                        // Note: energy_payload is NOT included in 'cost'
-                        // It will be deducted separately by the caller
-                        event = Some(crate::interaction::CausalEvent {
-                            id: crate::interaction::EventID(0), // Placeholder
-                            event_type: crate::interaction::EventType::Signal,
-                            source: crate::types::UniverseID(0), // Placeholder
-                            target: crate::types::UniverseID(target_id),
-                            energy_payload: 1.0, // Energy transmitted to target
-                            data: crate::types::StateVector::compress(&data),
-                            creation_step: 0, // Placeholder
-                            cause_id: None,
-                        });
-                        
-                        // Execution cost only (NOT including payload)
-                        cost += 0.001 + (len as f64 * 0.0001); // Small overhead for signal processing
-                        next_ip += 3 + len;
-                    } else {
-                        next_ip += 1; // Fault
-                    }
+            OpCode::Wait => {
+                // WAIT [type] - re-executed after waking, so it falls through once
+                // a matching message is queued
+                let filter = crate::interaction::EventType::from_code(args[0] as u8);
+                cost += op_cost.charge(0);
+                if !inbox.iter().any(|m| filter.is_none_or(|t| m.event_type == t)) {
+                    *execution_state = ExecutionState::Blocked(filter);
+                    return Ok((ip, cost, None));
+                }
+            }
+            OpCode::Handler => {
+                // HANDLER [type] [addr] - the kernel dispatches on delivery
+                let (code, handler) = (args[0] as u8, args[1]);
+                let event_type = crate::interaction::EventType::from_code(code)
+                    .ok_or(ProcessorFault::InvalidHandler)?;
+                if handler == 0 {
+                    handlers.remove(&event_type);
                 } else {
-                    next_ip += 1;
+                    check_bounds(state, handler, 1)?;
+                    handlers.insert(event_type, handler);
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::Entangle => {
-                // ENTANGLE [target_id] [strength]
-                if ip + 2 < state.len() {
-                    let target_id = state[ip+1] as u64;
-                    let strength = state[ip+2] as f64 / 255.0;
-                    
-                    // Signals interaction creation to kernel
-                    event = Some(crate::interaction::CausalEvent {
-                        id: crate::interaction::EventID(0),
-                        event_type: crate::interaction::EventType::Entangle,
-                        source: crate::types::UniverseID(0),
-                        target: crate::types::UniverseID(target_id),
-                        energy_payload: strength * 10.0, // Cost of interaction
-                        data: crate::types::StateVector::from_raw(vec![state[ip+2]]),
-                        creation_step: 0,
-                        cause_id: None,
-                    });
-                    
-                    cost += 5.0; // High cost for entanglement
-                    next_ip += 2;
+            OpCode::Iret => {
+                // IRET - resume where the handler interrupted and unmask dispatch
+                next_ip = handler_return.take().ok_or(ProcessorFault::InvalidHandler)?;
+                cost += op_cost.charge(0);
+            }
+            OpCode::SelfInfo | OpCode::SelfInfoReg => {
+                // SELF [field] [dest] / SELFR [reg] [field]
+                let field = if insn.opcode == OpCode::SelfInfo { args[0] } else { args[1] };
+                let fixed = |value: f64| (value * FIXED_POINT_SCALE) as u32; // Saturating
+                let value = match SelfField::from_code(field as u8) {
+                    Some(SelfField::Energy) => fixed(universe.energy),
+                    Some(SelfField::Entropy) => fixed(universe.entropy),
+                    Some(SelfField::Stability) => fixed(universe.stability_score),
+                    Some(SelfField::Id) => universe.id.0.min(u32::MAX as u64) as u32,
+                    Some(SelfField::Timeline) => universe.timeline_index.clamp(0, u32::MAX as i64) as u32,
+                    Some(SelfField::Budget) => universe.slice_budget,
+                    Some(SelfField::InboxDepth) => inbox.len() as u32,
+                    None => 0, // Unknown fields read as 0, like OBSERVE
+                };
+                if insn.opcode == OpCode::SelfInfoReg {
+                    registers[args[0]] = value;
+                } else if write_int(state, args[1], 4, value)? {
+                    cost += costs.erasure(4);
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::Observe => {
+            OpCode::Signal | OpCode::SignalTo | OpCode::SignalInd => {
+                // SIGNAL [target_id] [len] [data...]
+                // SIGNAL @[target_addr] [len] [data...]
+                // SIGNALI [target_addr] [buf_addr] [len_addr]
+                let target_id = match insn.opcode {
+                    OpCode::Signal => args[0] as u64,
+                    _ => read_universe_id(state, args[0])?,
+                };
+                let data = match insn.opcode {
+                    OpCode::SignalInd => {
+                        check_bounds(state, args[2], 1)?;
+                        let len = state[args[2]] as usize;
+                        check_bounds(state, args[1], len)?;
+                        state[args[1]..args[1] + len].to_vec()
+                    }
+                    _ => insn.payload.clone().map(|range| state[range].to_vec()).unwrap_or_default(),
+                };
+                let len = data.len();
+                
+                // Create event to be sent
+                // Note: energy_payload is NOT included in 'cost'
+                // It will be deducted separately by the caller
+                event = Some(crate::interaction::CausalEvent {
+                    id: crate::interaction::EventID(0), // Placeholder
+                    event_type: crate::interaction::EventType::Signal,
+                    source: crate::types::UniverseID(0), // Placeholder
+                    target: crate::types::UniverseID(target_id),
+                    energy_payload: 1.0, // Energy transmitted to target
+                    data: crate::types::StateVector::compress(&data),
+                    creation_step: 0, // Placeholder
+                    cause_id: None,
+                });
+                
+                // Execution cost only (NOT including payload)
+                cost += op_cost.charge(len);
+            }
+            OpCode::Entangle | OpCode::EntangleInd => {
+                // ENTANGLE [target_id] [strength]
+                let target_id = match insn.opcode {
+                    OpCode::Entangle => args[0] as u64,
+                    _ => read_universe_id(state, args[0])?,
+                };
+                let strength = args[1] as f64 / 255.0;
+                
+                // Signals interaction creation to kernel
+                event = Some(crate::interaction::CausalEvent {
+                    id: crate::interaction::EventID(0),
+                    event_type: crate::interaction::EventType::Entangle,
+                    source: crate::types::UniverseID(0),
+                    target: crate::types::UniverseID(target_id),
+                    energy_payload: strength * 10.0, // Cost of interaction
+                    data: crate::types::StateVector::from_raw(vec![args[1] as u8]),
+                    creation_step: 0,
+                    cause_id: None,
+                });
+                
+                cost += op_cost.charge(0);
+            }
+            OpCode::Observe | OpCode::Observe32 | OpCode::ObserveInd => {
                 // OBSERVE [target_id] [meta_type] [dest]
                 // 0=Energy, 1=Entropy, 2=Stability
-                if ip + 3 < state.len() {
-                    // This is synchronous in the kernel loop
-                    event = Some(crate::interaction::CausalEvent {
-                        id: crate::interaction::EventID(0),
-                        event_type: crate::interaction::EventType::Observation,
-                        source: crate::types::UniverseID(0),
-                        target: crate::types::UniverseID(state[ip+1] as u64),
-                        energy_payload: 0.1,
-                        data: crate::types::StateVector::from_raw(vec![state[ip+2], state[ip+3]]),
-                        creation_step: 0,
-                        cause_id: None,
-                    });
-                    cost += 0.5;
-                    next_ip += 3;
-                }
+                // This is synchronous in the kernel loop; the last data byte is
+                // the result width (1 = scaled byte, 4 = u32 in thousandths)
+                let target_id = match insn.opcode {
+                    OpCode::ObserveInd => read_universe_id(state, args[0])?,
+                    _ => args[0] as u64,
+                };
+                let [dest_lo, dest_hi] = addr_bytes(args[2]);
+                let width = if insn.opcode == OpCode::Observe32 { 4 } else { 1 };
+                event = Some(crate::interaction::CausalEvent {
+                    id: crate::interaction::EventID(0),
+                    event_type: crate::interaction::EventType::Observation,
+                    source: crate::types::UniverseID(0),
+                    target: crate::types::UniverseID(target_id),
+                    energy_payload: 0.1,
+                    data: crate::types::StateVector::from_raw(vec![args[1] as u8, dest_lo, dest_hi, width]),
+                    creation_step: 0,
+                    cause_id: None,
+                });
+                cost += op_cost.charge(0);
             }
             OpCode::Revert => {
                 // REVERT [steps]
-                if ip + 1 < state.len() {
-                    // Local timeline correction signal
-                    event = Some(crate::interaction::CausalEvent {
-                        id: crate::interaction::EventID(0),
-                        event_type: crate::interaction::EventType::Reversion,
-                        source: crate::types::UniverseID(0),
-                        target: crate::types::UniverseID(0), // Self
-                        energy_payload: state[ip+1] as f64 * 2.0,
-                        data: crate::types::StateVector::from_raw(vec![state[ip+1]]),
-                        creation_step: 0,
-                        cause_id: None,
-                    });
-                    cost += 2.0;
-                    next_ip += 1;
-                }
+                // Local timeline correction signal
+                event = Some(crate::interaction::CausalEvent {
+                    id: crate::interaction::EventID(0),
+                    event_type: crate::interaction::EventType::Reversion,
+                    source: crate::types::UniverseID(0),
+                    target: crate::types::UniverseID(0), // Self
+                    energy_payload: args[0] as f64 * 2.0,
+                    data: crate::types::StateVector::from_raw(vec![args[0] as u8]),
+                    creation_step: 0,
+                    cause_id: None,
+                });
+                cost += op_cost.charge(0);
             }
-            OpCode::Branch => {
+            OpCode::Branch | OpCode::BranchInd => {
                 // BRANCH [energy] [dest_addr_id]
-                if ip + 2 < state.len() {
-                    event = Some(crate::interaction::CausalEvent {
-                        id: crate::interaction::EventID(0),
-                        event_type: crate::interaction::EventType::Branch,
-                        source: crate::types::UniverseID(0),
-                        target: crate::types::UniverseID(0),
-                        energy_payload: state[ip+1] as f64,
-                        data: crate::types::StateVector::from_raw(vec![state[ip+2]]),
-                        creation_step: 0,
-                        cause_id: None,
-                    });
-                    cost += 10.0;
-                    next_ip += 2;
-                }
+                // BRANCH @[energy_addr] [dest_addr_id]
+                let energy = match insn.opcode {
+                    OpCode::Branch => args[0],
+                    _ => {
+                        check_bounds(state, args[0], 1)?;
+                        state[args[0]] as usize
+                    }
+                };
+                // Narrow BRANCH keeps its one-byte id field; the wide and indirect forms take two
+                let id_len = if insn.wide || insn.opcode == OpCode::BranchInd { UNIVERSE_ID_LEN } else { 1 };
+                check_bounds(state, args[1], id_len)?;
+                let [dest_lo, dest_hi] = addr_bytes(args[1]);
+                event = Some(crate::interaction::CausalEvent {
+                    id: crate::interaction::EventID(0),
+                    event_type: crate::interaction::EventType::Branch,
+                    source: crate::types::UniverseID(0),
+                    target: crate::types::UniverseID(0),
+                    energy_payload: energy as f64,
+                    data: crate::types::StateVector::from_raw(vec![dest_lo, dest_hi, id_len as u8]),
+                    creation_step: 0,
+                    cause_id: None,
+                });
+                cost += op_cost.charge(0);
             }
             OpCode::MemAlloc => {
-                // MEM_ALLOC [v_addr_reg] [size_reg]
-                if ip + 2 < state.len() {
-                    // In this demo, we just simulate the allocation cost
-                    cost += 1.0; 
-                    next_ip += 2;
+                // MEM_ALLOC [v_addr] [pages]
+                let first = mappable_page(state, memory_sys, args[0])?;
+                let pages = first..first + args[1];
+                if pages.is_empty() || pages.clone().any(|p| memory_sys.page_table.contains_key(&p)) {
+                    return Err(ProcessorFault::PageFault);
                 }
+                let ids: Vec<_> = pages.map(|v_page| memory_sys.allocate_page(pool, v_page)).collect();
+                registers[0] = ids[0].0 as u32;
+                cost += op_cost.charge(args[1] * memory_sys.page_size);
             }
             OpCode::MemMap => {
-                // MEM_MAP [v_addr_reg] [p_id_reg]
-                if ip + 2 < state.len() {
-                    // This is the core of Memory Entanglement
-                    cost += 2.0;
-                    next_ip += 2;
+                // MEM_MAP [v_addr] [p_id_reg]
+                // This is the core of Memory Entanglement
+                let v_page = mappable_page(state, memory_sys, args[0])?;
+                let id = super::memory::PhysicalPageID(registers[args[1]] as u64);
+                if !memory_sys.may_map(pool, id, universe_id) || !memory_sys.map_page(pool, v_page, id) {
+                    return Err(ProcessorFault::PageFault);
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::MemSwap => {
-                // MEM_SWAP [v_addr_reg]
-                if ip + 1 < state.len() {
-                    let v_addr = state[ip+1] as usize;
-                    let page_index = v_addr / memory_sys.page_size;
-                    
-                    if let Some(&p_id) = memory_sys.page_table.get(&page_index) {
-                        memory_sys.swap_to_ground_state(p_id);
-                    }
-                    
-                    cost += 0.5;
-                    next_ip += 1;
+            OpCode::MemShare => {
+                // MEM_SHARE [v_addr] [universe_id_reg]
+                let grantee = crate::types::UniverseID(registers[args[1]] as u64);
+                if memory_sys.share_page(pool, args[0] / memory_sys.page_size, grantee).is_none() {
+                    return Err(ProcessorFault::PageFault);
                 }
+                cost += op_cost.charge(0);
             }
-            OpCode::Halt => {
-                // Do not advance IP (spin) or signal termination
-                return Ok((ip, 0.0, None));
+            OpCode::MemSwap => {
+                // MEM_SWAP [v_addr]
+                memory_sys.swap_to_ground_state(pool, args[0]);
+                cost += op_cost.charge(0);
+            }
+            OpCode::Halt | OpCode::Exit => {
+                // Stop in place; the kernel no longer schedules the universe
+                let code = if insn.opcode == OpCode::Exit { args[0] as u8 } else { 0 };
+                *execution_state = ExecutionState::Halted(code);
+                return Ok((ip, cost + op_cost.charge(0), None));
             }
         }
 
         Ok((next_ip, cost, event))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{StateVector, UniverseID};
+
+    fn universe_with(state: Vec<u8>) -> Universe {
+        let mut u = Universe::new(UniverseID(1), 100.0);
+        u.state_vector = StateVector::new_raw(state);
+        u
+    }
+
+    /// Step a universe that maps no pages
+    fn run(u: &mut Universe) -> Result<(usize, f64, Option<crate::interaction::CausalEvent>)> {
+        UniversalProcessor::step(u, &mut PagePool::new(), &EnergyCostModel::default())
+    }
+
+    fn step_at(u: &mut Universe, ip: usize) -> usize {
+        u.instruction_pointer = ip;
+        run(u).unwrap().0
+    }
+
+    #[test]
+    fn test_narrow_instruction_decoding() {
+        let insn = Instruction::decode(&[0x03, 10, 20, 5], 0).unwrap();
+        assert_eq!(insn.opcode, OpCode::AtomCopy);
+        assert!(!insn.wide);
+        assert_eq!(&insn.args[..3], &[10, 20, 5]);
+        assert_eq!(insn.len, 4);
+
+        // Malformed instructions decode to the fault they raise
+        assert_eq!(Instruction::decode(&[0x03, 10], 0), Err(ProcessorFault::TruncatedOperand));
+        assert_eq!(Instruction::decode(&[0xEE], 0), Err(ProcessorFault::InvalidOpcode));
+        assert_eq!(Instruction::decode(&[0x30, 9, 1], 0), Err(ProcessorFault::InvalidRegister));
+    }
+
+    #[test]
+    fn test_faults_do_not_advance() {
+        let cases: [(&[u8], ProcessorFault); 4] = [
+            (&[0x01, 200, 1], ProcessorFault::OutOfBounds), // SET beyond the state vector
+            (&[0x10, 99], ProcessorFault::OutOfBounds),     // JUMP beyond the state vector
+            (&[0x21], ProcessorFault::StackUnderflow),      // RET with an empty stack
+            (&[0xEE], ProcessorFault::InvalidOpcode),
+        ];
+        for (code, expected) in cases {
+            let mut u = universe_with(code.to_vec());
+            let err = run(&mut u).unwrap_err();
+            assert_eq!(err, KernelError::ProcessorFault { fault: expected, ip: 0 });
+        }
+    }
+
+    #[test]
+    fn test_wide_set_reaches_high_state() {
+        let mut state = vec![0u8; 2048];
+        state[..5].copy_from_slice(&[0xFE, 0x01, 0x00, 0x07, 42]); // SET.W 1792 42
+        let mut u = universe_with(state);
+        assert_eq!(step_at(&mut u, 0), 5);
+        assert_eq!(u.state_vector.raw()[1792], 42);
+    }
+
+    #[test]
+    fn test_wide_call_and_ret() {
+        let mut state = vec![0u8; 1024];
+        state[..4].copy_from_slice(&[0xFE, 0x20, 0x00, 0x03]); // CALL.W 768
+        state[768..770].copy_from_slice(&[0xFE, 0x21]); // RET.W
+        let mut u = universe_with(state);
+        assert_eq!(step_at(&mut u, 0), 768);
+        assert_eq!(u.stack.returns(), &[0, 4]);
+        assert_eq!(step_at(&mut u, 768), 4);
+        assert!(u.stack.is_empty());
+        assert!(u.state_vector.raw()[4..768].iter().all(|&b| b == 0)); // Memory untouched
+    }
+
+    #[test]
+    fn test_event_handler_dispatch_and_iret() {
+        use crate::interaction::EventType;
+        // HANDLER 2 6; NOP; NOP; NOP; IRET
+        let mut state = vec![0xE3, 2, 6, 0x00, 0x00, 0x00, 0xE4];
+        state.resize(16, 0);
+        let mut u = universe_with(state);
+        u.instruction_pointer = step_at(&mut u, 0);
+        assert_eq!(u.instruction_pointer, 3);
+        assert_eq!(u.handlers.get(&EventType::Signal), Some(&6));
+
+        let energy = u.energy;
+        assert!(!u.dispatch_handler(EventType::EnergyTransfer, 0.005)); // No handler
+        assert!(u.dispatch_handler(EventType::Signal, 0.005));
+        assert_eq!((u.instruction_pointer, u.handler_return), (6, Some(3)));
+        assert!((energy - u.energy - 0.005).abs() < 1e-12);
+        assert!(!u.dispatch_handler(EventType::Signal, 0.005)); // Masked
+
+        assert_eq!(run(&mut u).unwrap().0, 3); // IRET
+        assert_eq!(u.handler_return, None);
+        u.instruction_pointer = 6;
+        let err = run(&mut u).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::InvalidHandler, ip: 6 });
+
+        // Unknown event types cannot be handled
+        let mut u = universe_with(vec![0xE3, 0, 1]);
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::InvalidHandler, ip: 0 });
+    }
+
+    #[test]
+    fn test_self_introspection() {
+        // SELF 0 40; SELF 2 44; SELFR r1 3; SELFR r2 6; SELF 9 48
+        let mut state = vec![0xE8, 0, 40, 0xE8, 2, 44, 0xE9, 1, 3, 0xE9, 2, 6, 0xE8, 9, 48];
+        state.resize(64, 0xAA);
+        let mut u = universe_with(state);
+        u.energy = 12.3456;
+        u.stability_score = 0.5;
+        u.deliver(crate::interaction::CausalEvent::new(
+            crate::interaction::EventID(1), crate::interaction::EventType::Signal,
+            UniverseID(2), UniverseID(1), 0.0, StateVector::empty(), 0,
+        ));
+
+        let mut ip = 0;
+        for _ in 0..5 {
+            ip = step_at(&mut u, ip);
+        }
+        let state = u.state_vector.raw();
+        assert_eq!(read_le(&state[40..44]), 12345); // Millijoules, truncated
+        assert_eq!(read_le(&state[44..48]), 500);
+        assert_eq!((u.registers[1], u.registers[2]), (1, 1));
+        assert_eq!(read_le(&state[48..52]), 0); // Unknown field
+    }
+
+    #[test]
+    fn test_stack_depth_is_bounded() {
+        // PUSH 10; JUMP 0
+        let mut u = universe_with(vec![0x22, 10, 0x10, 0, 0, 0, 0, 0, 0, 0, 7]);
+        u.stack = CallStack::new(3);
+        for _ in 0..3 {
+            step_at(&mut u, 0);
+        }
+        assert_eq!(u.stack.as_slice(), &[7, 7, 7]);
+        let err = run(&mut u).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::StackOverflow, ip: 0 });
+
+        // POP 10 on an empty stack
+        let mut u = universe_with(vec![0x23, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::StackUnderflow, ip: 0 });
+    }
+
+    #[test]
+    fn test_wide_call_and_ret_are_all_or_nothing() {
+        // CALL.W 0 with room for only one byte
+        let mut u = universe_with(vec![0xFE, 0x20, 0, 0, 0, 0, 0, 0]);
+        u.stack = CallStack::new(2);
+        u.stack.push(9).unwrap();
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::StackOverflow, ip: 0 });
+        assert_eq!(u.stack.as_slice(), &[9]);
+
+        // RET.W with one byte on the stack
+        let mut u = universe_with(vec![0xFE, 0x21, 0, 0, 0, 0, 0, 0]);
+        u.stack.push(9).unwrap();
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::StackUnderflow, ip: 0 });
+        assert_eq!(u.stack.as_slice(), &[9]);
+
+        // CALL.W then RET.W round trip: high byte is pushed first
+        let mut state = vec![0u8; 512];
+        state[300..304].copy_from_slice(&[0xFE, 0x20, 0x00, 0x01]); // CALL.W 256
+        state[256..258].copy_from_slice(&[0xFE, 0x21]); // RET.W
+        let mut u = universe_with(state);
+        assert_eq!(step_at(&mut u, 300), 256);
+        assert_eq!(u.stack.returns(), &[1, 48]); // 304 = 0x0130
+        assert_eq!(step_at(&mut u, 256), 304);
+        assert!(u.stack.is_empty());
+
+        // A narrow CALL cannot return above 255
+        let mut state = vec![0u8; 512];
+        state[300..302].copy_from_slice(&[0x20, 0]); // CALL 0
+        let mut u = universe_with(state);
+        u.instruction_pointer = 300;
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::ReturnOutOfRange, ip: 300 });
+        assert!(u.stack.is_empty());
+    }
+
+    #[test]
+    fn test_immediate_arithmetic() {
+        let mut state = vec![0u8; 64];
+        state[..9].copy_from_slice(&[
+            0x07, 40, 5, // ADDI 40 5
+            0x08, 40, 2, // SUBI 40 2
+            0x09, 40, 3, // CMPI 40 3 [41] (result address follows)
+        ]);
+        state[9] = 41;
+        let mut u = universe_with(state);
+        let ip = step_at(&mut u, 0);
+        let ip = step_at(&mut u, ip);
+        step_at(&mut u, ip);
+        assert_eq!(u.state_vector.raw()[40], 3);
+        assert_eq!(u.state_vector.raw()[41], 0); // Equal
+    }
+
+    #[test]
+    fn test_register_loop_counter() {
+        let mut state = vec![0u8; 64];
+        state[..11].copy_from_slice(&[
+            0x30, 2, 10, // RSET r2 10
+            0x37, 2, 1,  // RSUBI r2 1
+            0x32, 50, 2, // RSTORE 50 r2
+            0x10, 3,     // JUMP 3
+        ]);
+        let mut u = universe_with(state);
+        let mut ip = 0;
+        for _ in 0..7 {
+            ip = step_at(&mut u, ip);
+        }
+        assert_eq!(u.registers[2], 8);
+        assert_eq!(u.state_vector.raw()[50], 8);
+    }
+
+    #[test]
+    fn test_alu_group() {
+        let mut state = vec![0u8; 64];
+        state[40] = 12;
+        state[41] = 5;
+        state[..14].copy_from_slice(&[
+            0x60, 40, 41, // MUL 40 41 -> 60
+            0x62, 40, 41, // MOD 40 41 -> 0
+            0x67, 40,     // NOT 40 -> 255
+            0x66, 40, 4,  // SHR 40 4 -> 15
+            0x63, 40, 41, // AND 40 41 -> 5
+        ]);
+        let mut u = universe_with(state);
+        let mut ip = 0;
+        for expected in [60, 0, 255, 15, 5] {
+            ip = step_at(&mut u, ip);
+            assert_eq!(u.state_vector.raw()[40], expected);
+        }
+    }
+
+    #[test]
+    fn test_multi_byte_arithmetic() {
+        let mut state = vec![0u8; 64];
+        state[..22].copy_from_slice(&[
+            0x50, 40, 0xFF, 0xFF, 0x00, 0x00, // SET32 40 65535
+            0x50, 44, 0x02, 0x00, 0x00, 0x00, // SET32 44 2
+            0x51, 40, 44,                     // ADD32 40 44 -> 65537
+            0x57, 3, 40,                      // RLOAD32 r3 40
+            0x46, 48, 50, 52,                 // CMPS16 48 50 [52]
+        ]);
+        state[48..50].copy_from_slice(&(-5i16).to_le_bytes());
+        state[50..52].copy_from_slice(&3i16.to_le_bytes());
+        let mut u = universe_with(state);
+        let mut ip = 0;
+        for _ in 0..5 {
+            ip = step_at(&mut u, ip);
+        }
+        assert_eq!(&u.state_vector.raw()[40..44], &65537u32.to_le_bytes());
+        assert_eq!(u.registers[3], 65537);
+        assert_eq!(u.state_vector.raw()[52], 255); // -5 < 3 when signed
+    }
+
+    #[test]
+    fn test_inbox_instructions() {
+        use crate::interaction::{CausalEvent, EventID, EventType};
+
+        let mut state = vec![0u8; 64];
+        state[..7].copy_from_slice(&[
+            0xE2, 0,      // WAIT 0
+            0xE0, 30,     // POLL 30
+            0xE1, 40, 2,  // RECV 40 2
+        ]);
+        let mut u = universe_with(state);
+
+        // Nothing queued: WAIT blocks in place
+        assert_eq!(step_at(&mut u, 0), 0);
+        assert_eq!(u.execution_state, ExecutionState::Blocked(None));
+
+        let message = CausalEvent::new(
+            EventID(1), EventType::Signal, UniverseID(9), UniverseID(1), 1.0,
+            StateVector::compress(b"hello"), 0,
+        );
+        assert!(u.deliver(message));
+        assert!(u.is_running());
+
+        let ip = step_at(&mut u, 0);
+        let ip = step_at(&mut u, ip);
+        assert_eq!(u.state_vector.raw()[30], 1);
+        step_at(&mut u, ip);
+        // Header (source 9, Signal, 2 bytes) followed by the truncated payload
+        assert_eq!(&u.state_vector.raw()[40..46], &[9, 0, 2, 2, b'h', b'e']);
+        assert!(u.inbox.is_empty());
+
+        // Empty inbox reports message type 0
+        step_at(&mut u, ip);
+        assert_eq!(u.state_vector.raw()[42], 0);
+    }
+
+    #[test]
+    fn test_universe_id_widths() {
+        use crate::interaction::{CausalEvent, EventID, EventType};
+
+        // Narrow BRANCH reserves one byte for the child id, BRANCH.W two
+        let mut state = vec![0u8; 64];
+        state[..3].copy_from_slice(&[0xF4, 10, 63]); // BRANCH 10 63
+        state[3..8].copy_from_slice(&[0xFE, 0xF4, 10, 62, 0]); // BRANCH.W 10 62
+        let mut u = universe_with(state);
+        let (_, _, event) = run(&mut u).unwrap();
+        assert_eq!(event.unwrap().data.raw(), &[63, 0, 1]);
+        u.instruction_pointer = 3;
+        let (_, _, event) = run(&mut u).unwrap();
+        assert_eq!(event.unwrap().data.raw(), &[62, 0, 2]);
+
+        // RECV faults rather than truncate a sender id beyond 16 bits
+        let mut state = vec![0u8; 48];
+        state[..3].copy_from_slice(&[0xE1, 40, 2]); // RECV 40 2
+        let mut u = universe_with(state);
+        let message = CausalEvent::new(
+            EventID(1), EventType::Signal, UniverseID(70000), UniverseID(1), 1.0,
+            StateVector::compress(b"hi"), 0,
+        );
+        assert!(u.deliver(message));
+        assert_eq!(run(&mut u).unwrap_err(), KernelError::ProcessorFault { fault: ProcessorFault::IdOutOfRange, ip: 0 });
+        assert_eq!(u.inbox.len(), 1);
+    }
+
+    #[test]
+    fn test_indirect_signal_operands() {
+        let mut state = vec![0u8; 64];
+        state[..4].copy_from_slice(&[0xF7, 40, 44, 42]); // SIGNALI 40 44 42
+        state[40..42].copy_from_slice(&300u16.to_le_bytes()); // Target id
+        state[42] = 3; // Length
+        state[44..47].copy_from_slice(b"abc");
+        let mut u = universe_with(state);
+
+        let (_, _, event) = run(&mut u).unwrap();
+        let event = event.unwrap();
+        assert_eq!(event.target, UniverseID(300));
+        assert_eq!(event.data.expand(), b"abc");
+    }
+
+    #[test]
+    fn test_indirect_load_store() {
+        let mut state = vec![0u8; 64];
+        state[..3].copy_from_slice(&[0x30, 0, 40]); // RSET r0 40
+        state[3..6].copy_from_slice(&[0x30, 1, 2]); // RSET r1 2
+        state[6..10].copy_from_slice(&[0x39, 2, 0, 1]); // LOAD r2 r0 1
+        state[10..14].copy_from_slice(&[0x3C, 50, 1, 2]); // STOREX 50 r1 r2
+        state[14..17].copy_from_slice(&[0x3D, 55, 48]); // LDP 55 48
+        state[40..44].copy_from_slice(&[7, 8, 9, 10]);
+        state[48] = 42; // Pointer to byte 42
+        let mut u = universe_with(state);
+
+        let mut ip = 0;
+        for _ in 0..5 {
+            ip = step_at(&mut u, ip);
+        }
+        assert_eq!(u.registers[2], 8);
+        assert_eq!(u.state_vector.raw()[52], 8);
+        assert_eq!(u.state_vector.raw()[55], 9);
+
+        // Computed addresses past the state vector fault unless a page is mapped there
+        let mut pool = PagePool::new();
+        let costs = EnergyCostModel::default();
+        u.registers[0] = 300;
+        u.instruction_pointer = 0;
+        u.state_vector.raw_mut()[..4].copy_from_slice(&[0x3A, 0, 0, 2]); // STORE r0 0 r2
+        let err = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::PageFault, ip: 0 });
+
+        u.memory.allocate_page(&mut pool, 1);
+        UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap();
+        assert_eq!(u.memory.read_byte(&mut pool, 300).map(|(b, _)| b), Some(8));
+    }
+
+    #[test]
+    fn test_mem_alloc_and_map() {
+        let mut state = vec![0u8; 64];
+        state[..4].copy_from_slice(&[0xFE, 0xA0, 0x00, 0x02]); // MEMALLOC.W 512 ...
+        state[4] = 2; // ... 2 pages
+        state[5..10].copy_from_slice(&[0xFE, 0xA1, 0x00, 0x08, 0]); // MEMMAP.W 2048 r0
+        state[10..14].copy_from_slice(&[0xA1, 16, 0, 0]); // MEMMAP 16 r0
+        let mut u = universe_with(state);
+        let mut pool = PagePool::new();
+        let costs = EnergyCostModel::default();
+
+        let (ip, cost, _) = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap();
+        assert!(cost > 2.0);
+        let first = super::super::memory::PhysicalPageID(u.registers[0] as u64);
+        assert_eq!(u.memory.page_table.get(&2), Some(&first));
+        assert!(u.memory.page_table.contains_key(&3));
+        assert_eq!(pool.len(), 2);
+
+        // Aliasing a page shares its bytes
+        u.instruction_pointer = ip;
+        let (ip, _, _) = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap();
+        u.memory.write_byte(&mut pool, 512, 77).unwrap();
+        assert_eq!(u.memory.read_byte(&mut pool, 2048).map(|(b, _)| b), Some(77));
+        assert_eq!(pool.pages[&first].entanglement_count, 2);
+
+        // Mapping over the state vector is a page fault
+        u.instruction_pointer = ip;
+        let err = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::PageFault, ip: 10 });
+
+        // An empty allocation has no page id to return
+        u.state_vector.raw_mut()[..5].copy_from_slice(&[0xFE, 0xA0, 0x00, 0x0A, 0]); // MEMALLOC.W 2560 0
+        u.instruction_pointer = 0;
+        let err = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::PageFault, ip: 0 });
+
+        // Unmapping everything reclaims the pages
+        u.memory.release_all(&mut pool);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_mem_map_requires_share() {
+        let mut pool = PagePool::new();
+        let costs = EnergyCostModel::default();
+        let mut owner = universe_with(vec![0u8; 64]);
+        let page = owner.memory.allocate_page(&mut pool, 2);
+
+        let mut state = vec![0u8; 64];
+        state[..5].copy_from_slice(&[0xFE, 0xA1, 0x00, 0x02, 0]); // MEMMAP.W 512 r0
+        let mut mapper = universe_with(state);
+        mapper.id = UniverseID(2);
+        mapper.registers[0] = page.0 as u32;
+
+        // Page ids are guessable, so an unshared page faults
+        let err = UniversalProcessor::step(&mut mapper, &mut pool, &costs).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::PageFault, ip: 0 });
+        assert!(mapper.memory.page_table.is_empty());
+
+        // MEMSHARE.W 512 r1 grants the universe in r1
+        owner.state_vector.raw_mut()[..5].copy_from_slice(&[0xFE, 0xA3, 0x00, 0x02, 1]);
+        owner.registers[1] = 2;
+        UniversalProcessor::step(&mut owner, &mut pool, &costs).unwrap();
+        UniversalProcessor::step(&mut mapper, &mut pool, &costs).unwrap();
+        assert_eq!(mapper.memory.page_table.get(&2), Some(&page));
+        assert_eq!(pool.pages[&page].entanglement_count, 2);
+
+        // A page still shared copy-on-write with a branch cannot be shared
+        let mut branch = MultiversalMemory::new();
+        let private = owner.memory.allocate_page(&mut pool, 3);
+        owner.memory.share_with_branch(&mut branch, &mut pool);
+        owner.state_vector.raw_mut()[3] = 0x03; // MEMSHARE.W 768 r1
+        owner.instruction_pointer = 0;
+        let err = UniversalProcessor::step(&mut owner, &mut pool, &costs).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::PageFault, ip: 0 });
+        assert!(pool.pages[&private].grants.is_empty());
+    }
+
+    #[test]
+    fn test_custom_cost_model() {
+        let costs = EnergyCostModel::from_json(r#"{
+            "cycle": 0.0,
+            "erasure_per_byte": 1.0,
+            "opcodes": { "Add16": { "base": 0.5, "per_byte": 0.25 } }
+        }"#).unwrap();
+        let mut state = vec![0u8; 16];
+        state[..3].copy_from_slice(&[0x41, 10, 12]); // ADD16 10 12
+        state[3..7].copy_from_slice(&[0x40, 14, 0x01, 0x01]); // SET16 14 0x0101
+        let mut u = universe_with(state);
+        let mut pool = PagePool::new();
+
+        let (ip, cost, _) = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap();
+        assert_eq!(cost, 1.0);
+        u.instruction_pointer = ip;
+        let (_, cost, _) = UniversalProcessor::step(&mut u, &mut pool, &costs).unwrap();
+        assert_eq!(cost, 2.0); // Two bytes erased, SET16 itself is free
+    }
+
+    #[test]
+    fn test_divide_by_zero_faults() {
+        let mut u = universe_with(vec![0x61, 10, 11, 0, 0, 0, 0, 0, 0, 0, 9, 0]);
+        let err = run(&mut u).unwrap_err();
+        assert_eq!(err, KernelError::ProcessorFault { fault: ProcessorFault::DivideByZero, ip: 0 });
+        assert_eq!(u.state_vector.raw()[10], 9);
+    }
+}
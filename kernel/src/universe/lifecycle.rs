@@ -13,6 +13,7 @@ impl Universe {
     /// - Half of parent's energy
     /// - Current entropy level
     /// - Zero stability initially (must stabilize)
+    /// - Page table (the kernel shares mapped pages copy-on-write)
     ///
     /// # Arguments
     ///
@@ -58,6 +59,14 @@ impl Universe {
             last_evolution: 0,
             is_compressed: self.is_compressed,
             instruction_pointer: self.instruction_pointer,
+            registers: self.registers,
+            stack: self.stack.clone(),
+            last_fault: self.last_fault,
+            slice_budget: 0,
+            execution_state: self.execution_state,
+            inbox: std::collections::VecDeque::new(),
+            handlers: self.handlers.clone(),
+            handler_return: self.handler_return,
             memory: self.memory.clone(),
             shield_strength: self.shield_strength, // Inherit shield strength
         };
@@ -177,6 +186,11 @@ impl Universe {
     pub fn snapshot(&self) -> UniverseSnapshot {
         UniverseSnapshot {
             state_vector: self.state_vector.clone(),
+            registers: self.registers,
+            stack: self.stack.clone(),
+            execution_state: self.execution_state,
+            handlers: self.handlers.clone(),
+            handler_return: self.handler_return,
             energy: self.energy,
             entropy: self.entropy,
             stability_score: self.stability_score,
@@ -195,6 +209,11 @@ impl Universe {
     /// - LAW 2: Entropy can only increase, so we take max
     pub fn restore_from_snapshot(&mut self, snapshot: UniverseSnapshot) {
         self.state_vector = snapshot.state_vector;
+        self.registers = snapshot.registers;
+        self.stack = snapshot.stack;
+        self.execution_state = snapshot.execution_state;
+        self.handlers = snapshot.handlers;
+        self.handler_return = snapshot.handler_return;
         self.energy = snapshot.energy;
         // Entropy can only increase (LAW 2)
         self.entropy = self.entropy.max(snapshot.entropy);
@@ -208,6 +227,16 @@ impl Universe {
 pub struct UniverseSnapshot {
     /// State vector at snapshot time
     pub state_vector: StateVector,
+    /// Register file
+    pub registers: crate::universe::isa::RegisterFile,
+    /// Call stack
+    pub stack: crate::universe::isa::CallStack,
+    /// Execution state
+    pub execution_state: crate::universe::universe::ExecutionState,
+    /// Registered event handlers
+    pub handlers: std::collections::HashMap<crate::interaction::EventType, usize>,
+    /// IRET return address of the handler running at snapshot time
+    pub handler_return: Option<usize>,
     /// Energy level
     pub energy: f64,
     /// Entropy level
@@ -221,6 +250,8 @@ pub struct UniverseSnapshot {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::interaction::EventType;
+    use std::collections::HashMap;
 
     #[test]
     fn test_branch() {
@@ -317,4 +348,43 @@ mod tests {
         assert_eq!(universe.entropy, 30.0);
         assert_eq!(universe.stability_score, 0.7);
     }
+
+    #[test]
+    fn test_snapshot_restores_registers() {
+        let mut universe = Universe::new(UniverseID(1), 100.0);
+        universe.registers[3] = 42;
+        let snapshot = universe.snapshot();
+
+        universe.registers[3] = 0;
+        universe.restore_from_snapshot(snapshot);
+        assert_eq!(universe.registers[3], 42);
+    }
+
+    #[test]
+    fn test_snapshot_and_branch_copy_call_stack() {
+        let mut universe = Universe::new(UniverseID(1), 100.0);
+        universe.stack.push(7).unwrap();
+        let snapshot = universe.snapshot();
+        let branch = universe.branch(UniverseID(2)).unwrap();
+        assert_eq!(branch.stack.as_slice(), &[7]);
+
+        universe.stack.pop().unwrap();
+        universe.restore_from_snapshot(snapshot);
+        assert_eq!(universe.stack.as_slice(), &[7]);
+    }
+
+    #[test]
+    fn test_snapshot_restores_handlers() {
+        let mut universe = Universe::new(UniverseID(1), 100.0);
+        universe.handlers.insert(EventType::Signal, 12);
+        universe.handler_return = Some(30);
+        let snapshot = universe.snapshot();
+
+        universe.handlers.insert(EventType::Signal, 40);
+        universe.handlers.insert(EventType::Branch, 44);
+        universe.handler_return = None;
+        universe.restore_from_snapshot(snapshot);
+        assert_eq!(universe.handlers, HashMap::from([(EventType::Signal, 12)]));
+        assert_eq!(universe.handler_return, Some(30));
+    }
 }
@@ -1,71 +1,587 @@
 use serde::{Deserialize, Serialize};
-use crate::types::StateVector;
-use std::collections::HashMap;
+use super::page_store::{self, PageStore};
+use crate::types::{StateVector, UniverseID};
+use std::collections::{HashMap, HashSet};
 
 /// A page identifier in multiversal physical memory
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PhysicalPageID(pub u64);
 
+/// Access mass of a freshly allocated page, per byte (LAW 8)
+pub const PAGE_MASS_PER_BYTE: f64 = 0.00001;
+
+/// Energy to duplicate a copy-on-write page, per byte (LAW 8)
+pub const PAGE_COPY_ENERGY_PER_BYTE: f64 = 0.001;
+
+/// Kernel-level physical memory shared by every universe's page table
+///
+/// Pages are reference counted through `entanglement_count` and reclaimed
+/// when the last mapping is released. With a `PageStore` attached, ground-state
+/// pages are evicted whenever the resident set exceeds its budget.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PagePool {
+    /// Physical backing store (Shared between universes)
+    pub pages: HashMap<PhysicalPageID, PageData>,
+    /// Next physical page ID (monotonic counter)
+    next_id: u64,
+    /// Kernel evolution step, stamped on pages as they are accessed
+    #[serde(default)]
+    pub clock: u64,
+    /// Resident bytes allowed before ground-state pages are evicted
+    #[serde(default)]
+    pub resident_budget: Option<usize>,
+    /// Pages written out to the store
+    #[serde(default)]
+    pub evictions: u64,
+    /// Evicted pages faulted back in
+    #[serde(default)]
+    pub restores: u64,
+    /// Where evicted pages live
+    #[serde(skip)]
+    store: Option<Box<dyn PageStore>>,
+    /// Page versions held by the store, with the number of history snapshots pinning each
+    #[serde(skip)]
+    stored: HashMap<PhysicalPageID, HashMap<u32, usize>>,
+}
+
+impl Clone for PagePool {
+    /// Clones the page metadata and resident contents; the store is not
+    /// cloned, so evicted pages stay with the original pool's store. Use
+    /// `snapshot` for a history copy whose store versions stay pinned.
+    fn clone(&self) -> Self {
+        Self {
+            pages: self.pages.clone(),
+            next_id: self.next_id,
+            clock: self.clock,
+            resident_budget: self.resident_budget,
+            evictions: self.evictions,
+            restores: self.restores,
+            store: None,
+            stored: HashMap::new(),
+        }
+    }
+}
+
+impl PagePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a backing store for evicted pages
+    pub fn set_store(&mut self, store: Option<Box<dyn PageStore>>) {
+        self.store = store;
+        self.stored.clear();
+    }
+
+    /// Bytes of page content held in memory
+    pub fn resident_bytes(&self) -> usize {
+        self.pages.values().map(|p| p.content.size()).sum()
+    }
+
+    /// Number of page versions held by the store
+    pub fn stored_versions(&self) -> usize {
+        self.stored.values().map(HashMap::len).sum()
+    }
+
+    /// Evict the coldest ground-state pages until the resident set fits the budget
+    ///
+    /// Returns the number of pages evicted.
+    pub fn enforce_budget(&mut self) -> usize {
+        let (Some(budget), Some(store)) = (self.resident_budget, self.store.as_mut()) else {
+            return 0;
+        };
+        let mut resident: usize = self.pages.values().map(|p| p.content.size()).sum();
+        let mut candidates: Vec<(u64, PhysicalPageID)> = self.pages.iter()
+            .filter(|(_, p)| p.content.is_compressed && p.evicted.is_none())
+            .map(|(&id, p)| (p.last_access, id))
+            .collect();
+        candidates.sort_unstable_by_key(|&(last_access, id)| (last_access, id.0));
+
+        let mut evicted = 0;
+        for (_, id) in candidates {
+            if resident <= budget {
+                break;
+            }
+            let Some(page) = self.pages.get_mut(&id) else { continue };
+            let version = page_store::checksum(&page.content.data);
+            // A version the history still pins is already in the store
+            if let std::collections::hash_map::Entry::Vacant(slot) = self.stored.entry(id).or_default().entry(version) {
+                if let Err(e) = store.store(id, version, &page.content.data) {
+                    log::warn!("⚠️ Cannot evict page {}: {}", id.0, e);
+                    break;
+                }
+                slot.insert(0);
+            }
+            resident -= page.content.data.len();
+            page.evicted = Some(version);
+            page.content.data = Vec::new();
+            evicted += 1;
+        }
+        self.evictions += evicted as u64;
+        evicted
+    }
+
+    /// Fault an evicted page back in from the store
+    ///
+    /// Returns false if the page is missing or cannot be restored intact.
+    pub fn make_resident(&mut self, id: PhysicalPageID) -> bool {
+        let Some(page) = self.pages.get(&id) else { return false };
+        let Some(expected) = page.evicted else { return true };
+        let Some(loaded) = self.load_evicted(id, expected) else { return false };
+        if let Some(page) = self.pages.get_mut(&id) {
+            page.content.data = loaded;
+            page.evicted = None;
+        }
+        self.restores += 1;
+        self.discard_unpinned(id);
+        true
+    }
+
+    /// Read an evicted page version from the store, checking it against its checksum
+    fn load_evicted(&mut self, id: PhysicalPageID, expected: u32) -> Option<Vec<u8>> {
+        match self.store.as_mut().map(|store| store.load(id, expected)) {
+            Some(Ok(data)) if page_store::checksum(&data) == expected => Some(data),
+            Some(Err(e)) => {
+                log::warn!("⚠️ Cannot restore page {}: {}", id.0, e);
+                None
+            }
+            _ => {
+                log::warn!("⚠️ Cannot restore page {}: no matching copy in the store", id.0);
+                None
+            }
+        }
+    }
+
+    /// Remove a page's store versions that neither this pool nor any history snapshot uses
+    fn discard_unpinned(&mut self, id: PhysicalPageID) {
+        let current = self.pages.get(&id).and_then(|page| page.evicted);
+        let Some(versions) = self.stored.get_mut(&id) else { return };
+        let stale: Vec<u32> = versions.iter()
+            .filter(|&(&version, &pins)| pins == 0 && Some(version) != current)
+            .map(|(&version, _)| version)
+            .collect();
+        for version in stale {
+            versions.remove(&version);
+            if let Some(Err(e)) = self.store.as_mut().map(|store| store.remove(id, version)) {
+                log::warn!("⚠️ Cannot remove page {} from the store: {}", id.0, e);
+            }
+        }
+        if versions.is_empty() {
+            self.stored.remove(&id);
+        }
+    }
+
+    /// Copy the pool with every page read back in, for archives that must
+    /// stand without the store; this pool's pages stay evicted
+    pub fn resident_copy(&mut self) -> PagePool {
+        let mut copy = self.clone();
+        let evicted: Vec<(PhysicalPageID, u32)> = self.pages.iter()
+            .filter_map(|(&id, page)| page.evicted.map(|version| (id, version)))
+            .collect();
+        for (id, version) in evicted {
+            if let (Some(data), Some(page)) = (self.load_evicted(id, version), copy.pages.get_mut(&id)) {
+                page.content.data = data;
+                page.evicted = None;
+            }
+        }
+        copy
+    }
+
+    /// Copy the pool for the kernel's history
+    ///
+    /// Evicted pages stay evicted in the copy, which pins their store
+    /// versions until it is handed back to `unpin`, so history costs no
+    /// resident memory for them.
+    pub fn snapshot(&mut self) -> PagePool {
+        let copy = self.clone();
+        for (&id, page) in &copy.pages {
+            if let Some(version) = page.evicted {
+                *self.stored.entry(id).or_default().entry(version).or_default() += 1;
+            }
+        }
+        copy
+    }
+
+    /// Release the store versions pinned by a history copy from `snapshot`
+    pub fn unpin(&mut self, snapshot: &PagePool) {
+        for (&id, page) in &snapshot.pages {
+            let Some(version) = page.evicted else { continue };
+            if let Some(pins) = self.stored.get_mut(&id).and_then(|versions| versions.get_mut(&version)) {
+                *pins = pins.saturating_sub(1);
+            }
+            self.discard_unpinned(id);
+        }
+    }
+
+    /// Replace the pages with a history copy, keeping the store and its versions
+    ///
+    /// The copy's own pins stay counted: the caller unpins it, and every
+    /// later snapshot, once this pool has taken over their pages.
+    pub fn rewind_to(&mut self, snapshot: PagePool) {
+        self.pages = snapshot.pages;
+        self.next_id = snapshot.next_id;
+        self.clock = snapshot.clock;
+        self.resident_budget = snapshot.resident_budget;
+        self.evictions = snapshot.evictions;
+        self.restores = snapshot.restores;
+        let ids: Vec<PhysicalPageID> = self.stored.keys().copied().collect();
+        for id in ids {
+            self.discard_unpinned(id);
+        }
+    }
+
+    /// Create a zeroed page with a single reference
+    pub fn allocate(&mut self, page_size: usize) -> PhysicalPageID {
+        self.insert(PageData {
+            content: StateVector::new_raw(vec![0u8; page_size]),
+            entanglement_count: 1,
+            mass: page_size as f64 * PAGE_MASS_PER_BYTE,
+            last_access: self.clock,
+            evicted: None,
+            entangled: false,
+            grants: HashSet::new(),
+        })
+    }
+
+    /// Copy a page into a fresh page with a single reference
+    pub fn duplicate(&mut self, id: PhysicalPageID) -> Option<PhysicalPageID> {
+        if !self.make_resident(id) {
+            return None;
+        }
+        let mut page = self.pages.get(&id)?.clone();
+        page.entanglement_count = 1;
+        page.entangled = false;
+        page.grants.clear();
+        Some(self.insert(page))
+    }
+
+    fn insert(&mut self, page: PageData) -> PhysicalPageID {
+        let id = PhysicalPageID(self.next_id);
+        self.next_id += 1;
+        self.pages.insert(id, page);
+        id
+    }
+
+    /// Add a reference to a page; returns false if the page does not exist
+    pub fn retain(&mut self, id: PhysicalPageID) -> bool {
+        match self.pages.get_mut(&id) {
+            Some(page) => {
+                page.entanglement_count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop a reference to a page, reclaiming it when none remain
+    pub fn release(&mut self, id: PhysicalPageID) {
+        if let Some(page) = self.pages.get_mut(&id) {
+            page.entanglement_count = page.entanglement_count.saturating_sub(1);
+            if page.entanglement_count == 1 {
+                page.entangled = false;
+            }
+            if page.entanglement_count == 0 {
+                self.pages.remove(&id);
+                self.discard_unpinned(id);
+            }
+        }
+    }
+
+    /// Number of live physical pages
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Check if no pages are live
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// 'Swap' a page (Potentialize metadata via ParadoxLF)
+    ///
+    /// Returns false if the page is missing or already in ground state.
+    pub fn swap_to_ground_state(&mut self, id: PhysicalPageID) -> bool {
+        match self.pages.get_mut(&id) {
+            Some(page) if !page.content.is_compressed => {
+                // Compression converts kinetic state to potential state
+                // In a real OS, this would be swapping to disk/NVMe
+                // In ParadoxOS, this is just ParadoxLF compression
+                page.content = StateVector::compress(page.content.raw());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Multiversal Paging System (Phase 17)
 /// 
 /// Replaces traditional flat address spaces with a physical-weighted model.
 /// Law 8: Memory is Potential. Higher mass data increases gravity.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultiversalMemory {
-    /// Physical backing store (Shared between universes)
-    pub pages: HashMap<PhysicalPageID, PageData>,
     /// Thread-local virtual mapping (Virtual Page -> Physical ID)
     pub page_table: HashMap<usize, PhysicalPageID>,
+    /// Virtual pages inherited from a branch, copied before their first write
+    #[serde(default)]
+    pub copy_on_write: HashSet<usize>,
     /// Page size (Default: 256 bytes for ParadoxOS)
     pub page_size: usize,
+    /// Thermodynamic mass of the mapped pages, as of the last mapping change
+    #[serde(default)]
+    mass: f64,
+    /// Swap-in/swap-out counters
+    #[serde(default)]
+    pub stats: PagingStats,
+}
+
+/// Page movements between kinetic (raw) and ground (compressed) state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PagingStats {
+    /// Compressed pages expanded on access
+    pub swap_ins: u64,
+    /// Pages compressed to ground state
+    pub swap_outs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageData {
     pub content: StateVector,
-    /// Number of universes entangled with this page
+    /// Number of page table entries referencing this page
     pub entanglement_count: u32,
     /// Thermodynamic mass (Energy cost per access)
     pub mass: f64,
+    /// Kernel evolution step of the last access
+    #[serde(default)]
+    pub last_access: u64,
+    /// Checksum of the content while it is evicted to a `PageStore`
+    #[serde(default)]
+    pub evicted: Option<u32>,
+    /// Mapped into another address space by MEM_MAP; shared, never copied on write
+    #[serde(default)]
+    pub entangled: bool,
+    /// Universes the page was shared with by MEM_SHARE, and so may MEM_MAP it
+    #[serde(default)]
+    pub grants: HashSet<UniverseID>,
+}
+
+impl PageData {
+    /// Expand compressed content back to kinetic (raw) form
+    pub fn excite(&mut self) {
+        if self.content.is_compressed {
+            self.content = StateVector::new_raw(self.content.expand());
+        }
+    }
+
+    /// Gravitational mass: uncompressed data has more mass than compressed data (LAW 8)
+    fn gravity(&self) -> f64 {
+        (self.content.size() as f64) * 0.01 + self.mass
+    }
+}
+
+impl Default for MultiversalMemory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MultiversalMemory {
     pub fn new() -> Self {
         Self {
-            pages: HashMap::new(),
             page_table: HashMap::new(),
+            copy_on_write: HashSet::new(),
             page_size: 256,
+            mass: 0.0,
+            stats: PagingStats::default(),
+        }
+    }
+
+    /// Allocate a zeroed page from the pool and map it at a virtual page index
+    pub fn allocate_page(&mut self, pool: &mut PagePool, v_page: usize) -> PhysicalPageID {
+        let id = pool.allocate(self.page_size);
+        self.map(pool, v_page, id);
+        id
+    }
+
+    /// Map an existing page at a virtual page index (Memory Entanglement)
+    ///
+    /// Returns false if the page is not in the pool.
+    pub fn map_page(&mut self, pool: &mut PagePool, v_page: usize, id: PhysicalPageID) -> bool {
+        if !pool.retain(id) {
+            return false;
+        }
+        // Aliasing a page of our own keeps it private; a page from another universe is entangled
+        if let (false, Some(page)) = (self.page_table.values().any(|&mapped| mapped == id), pool.pages.get_mut(&id)) {
+            page.entangled = true;
+        }
+        self.map(pool, v_page, id);
+        true
+    }
+
+    /// Allow another universe to map the page at a virtual page index
+    ///
+    /// Returns `None` if nothing is mapped there, or if the page is still
+    /// shared copy-on-write with a branch whose timeline a mapper could
+    /// otherwise write into.
+    pub fn share_page(&mut self, pool: &mut PagePool, v_page: usize, grantee: UniverseID) -> Option<PhysicalPageID> {
+        let id = *self.page_table.get(&v_page)?;
+        let page = pool.pages.get_mut(&id)?;
+        if self.copy_on_write.contains(&v_page) {
+            if page.entanglement_count > 1 {
+                return None;
+            }
+            self.copy_on_write.remove(&v_page);
         }
+        page.grants.insert(grantee);
+        Some(id)
+    }
+
+    /// Whether `mapper`, owning this address space, may map a physical page
+    ///
+    /// A universe may alias its own private pages, and map other pages only
+    /// once their owner has shared them with it.
+    pub fn may_map(&self, pool: &PagePool, id: PhysicalPageID, mapper: UniverseID) -> bool {
+        let owned = self.page_table.iter()
+            .any(|(v_page, &mapped)| mapped == id && !self.copy_on_write.contains(v_page));
+        owned || pool.pages.get(&id).is_some_and(|page| page.grants.contains(&mapper))
+    }
+
+    /// Point a virtual page at an already-referenced physical page
+    fn map(&mut self, pool: &mut PagePool, v_page: usize, id: PhysicalPageID) {
+        if let Some(old) = self.page_table.insert(v_page, id) {
+            pool.release(old);
+        }
+        self.copy_on_write.remove(&v_page);
+        self.update_mass(pool);
+    }
+
+    /// Release every mapping, e.g. when the universe collapses
+    pub fn release_all(&mut self, pool: &mut PagePool) {
+        for (_, id) in self.page_table.drain() {
+            pool.release(id);
+        }
+        self.copy_on_write.clear();
+        self.mass = 0.0;
+    }
+
+    /// Share this address space with a branch's cloned page table
+    ///
+    /// Every mapped page gains a reference and becomes copy-on-write on both
+    /// sides, except entangled pages, which the branch joins as a live mapping.
+    pub fn share_with_branch(&mut self, branch: &mut MultiversalMemory, pool: &mut PagePool) {
+        for (&v_page, &id) in &self.page_table {
+            pool.retain(id);
+            if pool.pages.get(&id).is_some_and(|page| !page.entangled) {
+                self.copy_on_write.insert(v_page);
+            }
+        }
+        branch.page_table = self.page_table.clone();
+        branch.copy_on_write = self.copy_on_write.clone();
+        branch.mass = self.mass;
     }
 
     /// Access a page by virtual address
-    pub fn access_page(&self, v_addr: usize) -> Option<&PageData> {
+    pub fn access_page<'a>(&self, pool: &'a PagePool, v_addr: usize) -> Option<&'a PageData> {
         let page_index = v_addr / self.page_size;
-        self.page_table.get(&page_index).and_then(|id| self.pages.get(id))
+        self.page_table.get(&page_index).and_then(|id| pool.pages.get(id))
     }
 
-    /// Entangle with a physical page from another universe
-    pub fn entangle_page(&mut self, my_v_page: usize, peer_p_id: PhysicalPageID) {
-        self.page_table.insert(my_v_page, peer_p_id);
+    /// Read a byte through the page table, with the access energy
+    ///
+    /// The energy is the page's access mass, plus the excitation energy if
+    /// the page had to be expanded from ground state.
+    /// Returns `None` if the address is unmapped or beyond the page's content.
+    pub fn read_byte(&mut self, pool: &mut PagePool, v_addr: usize) -> Option<(u8, f64)> {
+        let (id, excitation) = self.excite_page(pool, v_addr)?;
+        let page = pool.pages.get(&id)?;
+        Some((*page.content.raw().get(v_addr % self.page_size)?, page.mass + excitation))
     }
 
-    /// Calculate total thermodynamic mass (Gravity)
-    pub fn total_mass(&self) -> f64 {
-        self.pages.values().map(|p| {
-            // Uncompressed data has more mass than compressed data (LAW 8)
-            let base_mass = (p.content.size() as f64) * 0.01;
-            base_mass + p.mass
-        }).sum()
+    /// Write a byte through the page table, with the access energy
+    ///
+    /// A copy-on-write page still shared with another universe is duplicated
+    /// first, and the copy is charged on top of the page's access mass (and
+    /// any excitation energy).
+    /// Returns whether the byte changed, or `None` if the address is unmapped.
+    pub fn write_byte(&mut self, pool: &mut PagePool, v_addr: usize, value: u8) -> Option<(bool, f64)> {
+        let v_page = v_addr / self.page_size;
+        let mut energy = 0.0;
+        if self.copy_on_write.remove(&v_page) {
+            let id = *self.page_table.get(&v_page)?;
+            if pool.pages.get(&id)?.entanglement_count > 1 {
+                let copy = pool.duplicate(id)?;
+                self.map(pool, v_page, copy);
+                energy += self.page_size as f64 * PAGE_COPY_ENERGY_PER_BYTE;
+            }
+        }
+
+        let (id, excitation) = self.excite_page(pool, v_addr)?;
+        let page = pool.pages.get_mut(&id)?;
+        energy += page.mass + excitation;
+        let slot = page.content.raw_mut().get_mut(v_addr % self.page_size)?;
+        let changed = *slot != value;
+        *slot = value;
+        Some((changed, energy))
     }
 
-    /// 'Swap' a page (Potentialize metadata via ParadoxLF)
-    pub fn swap_to_ground_state(&mut self, id: PhysicalPageID) {
-        if let Some(page) = self.pages.get_mut(&id) {
-            // Compression converts kinetic state to potential state
-            // In a real OS, this would be swapping to disk/NVMe
-            // In ParadoxOS, this is just ParadoxLF compression
-            let raw = page.content.expand();
-            page.content = StateVector::compress(&raw);
+    /// Page mapped at a virtual address, expanded so it can be accessed directly
+    ///
+    /// Stamps the access time and returns the excitation energy spent (LAW 8).
+    fn excite_page(&mut self, pool: &mut PagePool, v_addr: usize) -> Option<(PhysicalPageID, f64)> {
+        let id = *self.page_table.get(&(v_addr / self.page_size))?;
+        if !pool.make_resident(id) {
+            return None;
+        }
+        let clock = pool.clock;
+        let page = pool.pages.get_mut(&id)?;
+        page.last_access = clock;
+        if !page.content.is_compressed {
+            return Some((id, 0.0));
+        }
+        let excitation = page.content.potential_energy();
+        page.excite();
+        self.stats.swap_ins += 1;
+        self.update_mass(pool);
+        Some((id, excitation))
+    }
+
+    /// 'Swap' the page mapped at a virtual address to ground state
+    pub fn swap_to_ground_state(&mut self, pool: &mut PagePool, v_addr: usize) {
+        if let Some(&id) = self.page_table.get(&(v_addr / self.page_size)) {
+            if pool.swap_to_ground_state(id) {
+                self.stats.swap_outs += 1;
+                self.update_mass(pool);
+            }
         }
     }
+
+    /// Compress mapped pages not accessed for `cold_after` steps
+    ///
+    /// Returns the number of pages swapped out.
+    pub fn swap_out_cold(&mut self, pool: &mut PagePool, cold_after: u64) -> usize {
+        let cold: Vec<PhysicalPageID> = self.page_table.values()
+            .copied()
+            .filter(|id| pool.pages.get(id)
+                .is_some_and(|p| pool.clock.saturating_sub(p.last_access) >= cold_after))
+            .collect();
+        let swapped = cold.into_iter().filter(|&id| pool.swap_to_ground_state(id)).count();
+        if swapped > 0 {
+            self.stats.swap_outs += swapped as u64;
+            self.update_mass(pool);
+        }
+        swapped
+    }
+
+    /// Recompute the mass of the mapped pages
+    pub fn update_mass(&mut self, pool: &PagePool) {
+        self.mass = self.page_table.values()
+            .filter_map(|id| pool.pages.get(id))
+            .map(PageData::gravity)
+            .sum();
+    }
+
+    /// Calculate total thermodynamic mass (Gravity)
+    pub fn total_mass(&self) -> f64 {
+        self.mass
+    }
 }
@@ -1,8 +1,13 @@
+#[allow(clippy::module_inception)]
 pub mod universe;
 pub mod lifecycle;
 pub mod isa;
 pub mod memory;
+pub mod costs;
+pub mod verifier;
+pub mod page_store;
 
-pub use universe::Universe;
+pub use universe::{ExecutionState, Universe};
 pub use isa::{OpCode, UniversalProcessor};
 pub use lifecycle::UniverseSnapshot;
+pub use costs::EnergyCostModel;
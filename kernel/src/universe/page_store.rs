@@ -0,0 +1,133 @@
+//! Backing stores for ground-state pages
+//!
+//! Compressed pages can be evicted from the kernel's page pool to a
+//! `PageStore` and faulted back in on their next access. Every stored page
+//! carries a checksum that is verified when it is read back.
+//!
+//! Entries are keyed by page and version, the checksum of the evicted bytes,
+//! so the kernel's history can keep pointing at an eviction after the live
+//! page has been evicted again with other contents.
+
+use super::memory::PhysicalPageID;
+use crate::error::{KernelError, Result};
+use std::path::{Path, PathBuf};
+
+/// Storage for evicted page contents (the compressed ParadoxLF bytes)
+pub trait PageStore: std::fmt::Debug {
+    /// Persist one version of a page's bytes, alongside any other versions
+    fn store(&mut self, id: PhysicalPageID, version: u32, data: &[u8]) -> Result<()>;
+
+    /// Read a version of a page's bytes back, verifying their checksum
+    fn load(&mut self, id: PhysicalPageID, version: u32) -> Result<Vec<u8>>;
+
+    /// Forget a version that neither the pool nor its history references any more
+    fn remove(&mut self, id: PhysicalPageID, version: u32) -> Result<()>;
+}
+
+/// FNV-1a checksum of a page's bytes
+pub fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811C_9DC5u32, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Magic bytes at the start of every page file
+const PAGE_FILE_MAGIC: &[u8; 4] = b"PXPG";
+
+/// Page file header: magic, checksum (u32 LE), length (u32 LE)
+const PAGE_FILE_HEADER_LEN: usize = 12;
+
+/// A directory of page files, one file per stored page version
+#[derive(Debug)]
+pub struct DiskPageStore {
+    dir: PathBuf,
+}
+
+impl DiskPageStore {
+    /// Open (creating if needed) a page directory
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| KernelError::Generic {
+            message: format!("cannot create page store {}: {}", dir.display(), e),
+        })?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: PhysicalPageID, version: u32) -> PathBuf {
+        self.dir.join(format!("page-{}-{:08x}.bin", id.0, version))
+    }
+}
+
+fn store_error(id: PhysicalPageID, message: impl std::fmt::Display) -> KernelError {
+    KernelError::PageStoreError { page: id.0, message: message.to_string() }
+}
+
+impl PageStore for DiskPageStore {
+    fn store(&mut self, id: PhysicalPageID, version: u32, data: &[u8]) -> Result<()> {
+        let mut file = Vec::with_capacity(PAGE_FILE_HEADER_LEN + data.len());
+        file.extend_from_slice(PAGE_FILE_MAGIC);
+        file.extend_from_slice(&checksum(data).to_le_bytes());
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(data);
+        std::fs::write(self.path(id, version), file).map_err(|e| store_error(id, e))
+    }
+
+    fn load(&mut self, id: PhysicalPageID, version: u32) -> Result<Vec<u8>> {
+        let file = std::fs::read(self.path(id, version)).map_err(|e| store_error(id, e))?;
+        if file.len() < PAGE_FILE_HEADER_LEN || &file[..4] != PAGE_FILE_MAGIC {
+            return Err(store_error(id, "not a page file"));
+        }
+        let expected = u32::from_le_bytes([file[4], file[5], file[6], file[7]]);
+        let len = u32::from_le_bytes([file[8], file[9], file[10], file[11]]) as usize;
+        let data = &file[PAGE_FILE_HEADER_LEN..];
+        if data.len() != len || checksum(data) != expected {
+            return Err(store_error(id, "checksum mismatch"));
+        }
+        Ok(data.to_vec())
+    }
+
+    fn remove(&mut self, id: PhysicalPageID, version: u32) -> Result<()> {
+        match std::fs::remove_file(self.path(id, version)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(store_error(id, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> DiskPageStore {
+        let dir = std::env::temp_dir().join(format!("paradox-pages-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        DiskPageStore::open(dir).unwrap()
+    }
+
+    #[test]
+    fn test_disk_store_round_trip() {
+        let mut store = temp_store("round-trip");
+        store.store(PhysicalPageID(7), 1, b"ground state").unwrap();
+        store.store(PhysicalPageID(7), 2, b"excited state").unwrap();
+        assert_eq!(store.load(PhysicalPageID(7), 1).unwrap(), b"ground state");
+        assert_eq!(store.load(PhysicalPageID(7), 2).unwrap(), b"excited state");
+
+        store.remove(PhysicalPageID(7), 1).unwrap();
+        assert!(store.load(PhysicalPageID(7), 1).is_err());
+        assert!(store.load(PhysicalPageID(7), 2).is_ok());
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_disk_store_detects_corruption() {
+        let mut store = temp_store("corruption");
+        store.store(PhysicalPageID(1), 1, b"abcdef").unwrap();
+
+        let path = store.path(PhysicalPageID(1), 1);
+        let mut file = std::fs::read(&path).unwrap();
+        *file.last_mut().unwrap() ^= 0xFF;
+        std::fs::write(&path, file).unwrap();
+
+        let err = store.load(PhysicalPageID(1), 1).unwrap_err();
+        assert!(matches!(err, KernelError::PageStoreError { page: 1, .. }));
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+}
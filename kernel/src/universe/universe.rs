@@ -1,8 +1,25 @@
+use crate::interaction::{CausalEvent, EventType};
 use crate::types::{InteractionID, StateVector, UniverseID};
-use super::memory::MultiversalMemory;
-use std::collections::HashSet;
+use super::isa::{CallStack, FaultRecord, OpCode, RegisterFile, REGISTER_COUNT, TRAP_CODE_REGISTER, TRAP_IP_REGISTER};
+use super::costs::EnergyCostModel;
+use super::memory::{MultiversalMemory, PagePool};
+use std::collections::{HashMap, HashSet, VecDeque};
 use serde::{Deserialize, Serialize};
 
+/// Execution state of a universe's program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ExecutionState {
+    /// Executes an instruction each time it is scheduled
+    #[default]
+    Running,
+    /// Stopped by HALT or EXIT, with its exit code
+    Halted(u8),
+    /// Waiting for an incoming event (of the given type, or any type if `None`)
+    Blocked(Option<EventType>),
+    /// Stopped by an unhandled processor fault (see `last_fault`)
+    Faulted,
+}
+
 /// Universe - replaces process, thread, container, and VM
 ///
 /// A universe is a self-contained computational entity with:
@@ -23,6 +40,39 @@ pub struct Universe {
     /// Instruction Pointer (Execution State)
     pub instruction_pointer: usize,
 
+    /// Architectural register file (r0-r7)
+    #[serde(default)]
+    pub registers: RegisterFile,
+
+    /// Call and data stack (CALL/RET/PUSH/POP), separate from the state vector
+    #[serde(default)]
+    pub stack: CallStack,
+
+    /// Last processor fault raised by this universe's program
+    #[serde(default)]
+    pub last_fault: Option<FaultRecord>,
+
+    /// Instructions granted in the current time slice (read by SELF)
+    #[serde(default)]
+    pub slice_budget: u32,
+
+    /// Program execution state (only running universes are scheduled)
+    #[serde(default)]
+    pub execution_state: ExecutionState,
+
+    /// Delivered events waiting to be received by the program (FIFO)
+    #[serde(default)]
+    pub inbox: VecDeque<CausalEvent>,
+
+    /// Event handler addresses registered with HANDLER
+    #[serde(default)]
+    pub handlers: HashMap<EventType, usize>,
+
+    /// Instruction to resume at on IRET; set while a handler runs, which
+    /// masks further dispatch
+    #[serde(default)]
+    pub handler_return: Option<usize>,
+
     /// Multiversal Paging System (Phase 17)
     pub memory: MultiversalMemory,
 
@@ -79,6 +129,14 @@ impl Universe {
             last_evolution: 0,
             is_compressed: false,
             instruction_pointer: 0,
+            registers: [0; REGISTER_COUNT],
+            stack: CallStack::default(),
+            last_fault: None,
+            slice_budget: 0,
+            execution_state: ExecutionState::Running,
+            inbox: VecDeque::new(),
+            handlers: HashMap::new(),
+            handler_return: None,
             memory: MultiversalMemory::new(),
             shield_strength: 0.0,
         }
@@ -88,20 +146,21 @@ impl Universe {
     /// 
     /// Returns: (Option<CausalEvent>, execution_cost)
     /// The execution_cost should be added to global_energy by the kernel (as heat)
-    pub fn execute_step(&mut self) -> (Option<crate::interaction::CausalEvent>, f64) {
+    ///
+    /// A processor fault is recorded in `last_fault`, stops execution and is
+    /// returned as `KernelError::ProcessorFault` for the kernel's fault policy.
+    pub fn execute_step(&mut self, pool: &mut PagePool, costs: &EnergyCostModel) -> crate::error::Result<(Option<crate::interaction::CausalEvent>, f64)> {
         // Law 1: Execution requires energy
-        if self.energy < 0.001 {
-             return (None, 0.0);
+        if self.energy < 0.001 || !self.is_running() {
+             return Ok((None, 0.0));
         }
 
         // Access raw memory (must be uncompressed to execute)
         if self.state_vector.is_compressed {
-            return (None, 0.0);
+            return Ok((None, 0.0));
         }
 
-        let memory = &mut self.state_vector.data; 
-        
-        match crate::universe::UniversalProcessor::step(memory, self.instruction_pointer, &mut self.memory) {
+        match crate::universe::UniversalProcessor::step(self, pool, costs) {
             Ok((new_ip, cost, mut event)) => {
                 self.instruction_pointer = new_ip;
                 self.energy -= cost;
@@ -117,9 +176,96 @@ impl Universe {
                     }
                 }
                 
-                (event, cost) // Return event AND cost
+                Ok((event, cost)) // Return event AND cost
             },
-            Err(_) => (None, 0.0) // Execution fault
+            Err(e) => {
+                // Execution fault: the instruction pointer does not advance
+                if let crate::error::KernelError::ProcessorFault { fault, ip } = e {
+                    let code = self.state_vector.raw();
+                    let wide = code.get(ip) == Some(&(OpCode::Wide as u8));
+                    self.last_fault = Some(FaultRecord {
+                        fault,
+                        ip,
+                        opcode: code.get(ip + wide as usize).copied().unwrap_or(0),
+                        wide,
+                        step: self.last_evolution,
+                    });
+                    self.execution_state = ExecutionState::Faulted;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Resume execution at a fault handler
+    ///
+    /// The fault code is written to r7 and the faulting IP to r6.
+    pub fn enter_trap(&mut self, handler: usize) {
+        if let Some(record) = self.last_fault {
+            self.registers[TRAP_CODE_REGISTER] = record.fault.code() as u32;
+            self.registers[TRAP_IP_REGISTER] = record.ip as u32;
+        }
+        self.instruction_pointer = handler;
+        self.execution_state = ExecutionState::Running;
+    }
+
+    /// Check if the universe's program is running
+    pub fn is_running(&self) -> bool {
+        self.execution_state == ExecutionState::Running
+    }
+
+    /// Exit code, if the program has halted
+    pub fn exit_code(&self) -> Option<u8> {
+        match self.execution_state {
+            ExecutionState::Halted(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Queue a delivered event for the program and wake it if it is waiting
+    ///
+    /// Returns false if the inbox is full and the event was dropped.
+    pub fn deliver(&mut self, event: CausalEvent) -> bool {
+        if self.inbox.len() >= crate::constants::MAX_INBOX_EVENTS {
+            return false;
+        }
+        let event_type = event.event_type;
+        self.inbox.push_back(event);
+        self.wake(event_type);
+        true
+    }
+
+    /// Interrupt the program to run its handler for an event type
+    ///
+    /// Saves the instruction pointer for IRET and charges `cost`. Nothing
+    /// happens without a registered handler, while another handler runs, once
+    /// the program has stopped, or if the universe cannot pay. Returns true if
+    /// the handler was entered.
+    pub fn dispatch_handler(&mut self, event_type: EventType, cost: f64) -> bool {
+        let Some(&handler) = self.handlers.get(&event_type) else {
+            return false;
+        };
+        let interruptible = matches!(self.execution_state, ExecutionState::Running | ExecutionState::Blocked(_));
+        if self.handler_return.is_some() || !interruptible || self.energy < cost {
+            return false;
+        }
+        self.energy -= cost;
+        self.handler_return = Some(self.instruction_pointer);
+        self.instruction_pointer = handler;
+        self.execution_state = ExecutionState::Running;
+        true
+    }
+
+    /// Wake the universe if it is blocked on an event of this type
+    ///
+    /// Returns true if the universe resumed running.
+    pub fn wake(&mut self, event_type: EventType) -> bool {
+        match self.execution_state {
+            ExecutionState::Blocked(filter) if filter.is_none_or(|t| t == event_type) => {
+                self.execution_state = ExecutionState::Running;
+                true
+            }
+            _ => false,
         }
     }
 
@@ -240,6 +386,7 @@ impl Universe {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::universe::costs::OpCost;
 
     #[test]
     fn test_universe_creation() {
@@ -313,4 +460,63 @@ mod tests {
         u.update_stability();
         assert!(u.stability_score < 0.5);
     }
+
+    #[test]
+    fn test_exit_stops_execution() {
+        let mut u = Universe::new(UniverseID(1), 100.0);
+        u.state_vector = StateVector::new_raw(vec![0x00, 0xFC, 3, 0x00]); // NOP; EXIT 3
+        let mut pool = PagePool::new();
+        let costs = EnergyCostModel::default();
+
+        u.execute_step(&mut pool, &costs).unwrap();
+        u.execute_step(&mut pool, &costs).unwrap();
+        assert_eq!(u.execution_state, ExecutionState::Halted(3));
+        assert_eq!(u.exit_code(), Some(3));
+
+        // A halted universe no longer executes (or burns energy)
+        let energy = u.energy;
+        assert_eq!(u.execute_step(&mut pool, &costs).unwrap().1, 0.0);
+        assert_eq!(u.energy, energy);
+    }
+
+    #[test]
+    fn test_halt_and_exit_pay_their_cost() {
+        let mut costs = EnergyCostModel::default();
+        costs.opcodes.insert(OpCode::Halt, OpCost::new(0.5, 0.0));
+        costs.opcodes.insert(OpCode::Exit, OpCost::new(0.25, 0.0));
+        let mut pool = PagePool::new();
+
+        for (code, price) in [(vec![0xFF], 0.5), (vec![0xFC, 3], 0.25)] {
+            let mut u = Universe::new(UniverseID(1), 100.0);
+            u.state_vector = StateVector::new_raw(code);
+            u.execute_step(&mut pool, &costs).unwrap();
+            assert!(!u.is_running());
+            assert!((100.0 - u.energy - (costs.cycle + price)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_wake_on_matching_event() {
+        let mut u = Universe::new(UniverseID(1), 100.0);
+        u.execution_state = ExecutionState::Blocked(Some(EventType::Signal));
+        assert!(!u.wake(EventType::EnergyTransfer));
+        assert!(u.wake(EventType::Signal));
+        assert!(u.is_running());
+
+        u.execution_state = ExecutionState::Blocked(None);
+        assert!(u.wake(EventType::EnergyTransfer));
+    }
+
+    #[test]
+    fn test_deserialize_without_processor_fields() {
+        let mut u = Universe::new(UniverseID(1), 100.0);
+        u.registers[2] = 5;
+        let mut value = serde_json::to_value(&u).unwrap();
+        for field in ["registers", "last_fault", "execution_state", "inbox", "handlers", "handler_return", "slice_budget", "stack"] {
+            value.as_object_mut().unwrap().remove(field);
+        }
+        let u: Universe = serde_json::from_value(value).unwrap();
+        assert_eq!(u.registers, [0; REGISTER_COUNT]);
+        assert!(u.is_running());
+    }
 }
@@ -0,0 +1,184 @@
+//! Static bytecode verification
+//!
+//! Programs are decoded with a linear sweep before they are loaded. Every
+//! byte must belong to a well-formed instruction, and static control-flow
+//! targets (JUMP, JUMPIF, CALL, HANDLER) must land on an instruction boundary
+//! inside the program.
+
+use super::isa::{Instruction, OpCode, Operand, ProcessorFault};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// What is wrong with a program at some offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerifyIssue {
+    /// Byte is not an opcode (or a doubled WIDE prefix)
+    InvalidOpcode(u8),
+    /// Operands run past the end of the program
+    TruncatedOperand,
+    /// Inline payload length runs past the end of the program
+    PayloadOverrun,
+    /// Register operand outside r0-r7
+    InvalidRegister,
+    /// Control-flow target outside the program
+    TargetOutOfRange(usize),
+    /// Control-flow target in the middle of an instruction
+    MisalignedTarget(usize),
+}
+
+impl std::fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyIssue::InvalidOpcode(byte) => write!(f, "invalid opcode 0x{:02X}", byte),
+            VerifyIssue::TruncatedOperand => write!(f, "truncated operand"),
+            VerifyIssue::PayloadOverrun => write!(f, "payload runs past the end of the program"),
+            VerifyIssue::InvalidRegister => write!(f, "invalid register"),
+            VerifyIssue::TargetOutOfRange(target) => write!(f, "target {} outside the program", target),
+            VerifyIssue::MisalignedTarget(target) => write!(f, "target {} is not an instruction boundary", target),
+        }
+    }
+}
+
+/// An issue found at a program offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyFinding {
+    /// Offset of the offending instruction
+    pub offset: usize,
+    /// What is wrong
+    pub issue: VerifyIssue,
+}
+
+/// Result of verifying a program
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Program length in bytes
+    pub len: usize,
+    /// Well-formed instructions decoded
+    pub instructions: usize,
+    /// Problems found, in program order
+    pub findings: Vec<VerifyFinding>,
+}
+
+impl VerificationReport {
+    /// Check if the program passed verification
+    pub fn is_valid(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl std::fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} issue(s) in {} bytes", self.findings.len(), self.len)?;
+        for finding in &self.findings {
+            write!(f, "; at {}: {}", finding.offset, finding.issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Verify a program before it is loaded into a universe
+pub fn verify(code: &[u8]) -> VerificationReport {
+    verify_at(code, 0)
+}
+
+/// Verify code that will be loaded at `base`
+///
+/// Finding offsets and control-flow targets are absolute addresses.
+pub fn verify_at(code: &[u8], base: usize) -> VerificationReport {
+    let mut report = VerificationReport { len: code.len(), ..Default::default() };
+    let mut boundaries = HashSet::new();
+    let mut targets = Vec::new();
+
+    let mut ip = 0;
+    while ip < code.len() {
+        match Instruction::decode(code, ip) {
+            Ok(insn) => {
+                boundaries.insert(base + ip);
+                match insn.opcode {
+                    OpCode::Jump | OpCode::Call => targets.push((base + ip, insn.args[0])),
+                    OpCode::JumpIf => targets.push((base + ip, insn.args[1])),
+                    // Address 0 removes a handler rather than naming one
+                    OpCode::Handler if insn.args[1] != 0 => targets.push((base + ip, insn.args[1])),
+                    _ => {}
+                }
+                report.instructions += 1;
+                ip += insn.len;
+            }
+            Err(fault) => {
+                report.findings.push(VerifyFinding { offset: base + ip, issue: decode_issue(code, ip, fault) });
+                // Resynchronize on the next byte
+                ip += 1;
+            }
+        }
+    }
+
+    for (offset, target) in targets {
+        let issue = if !(base..base + code.len()).contains(&target) {
+            VerifyIssue::TargetOutOfRange(target)
+        } else if !boundaries.contains(&target) {
+            VerifyIssue::MisalignedTarget(target)
+        } else {
+            continue;
+        };
+        report.findings.push(VerifyFinding { offset, issue });
+    }
+    report.findings.sort_by_key(|finding| finding.offset);
+    report
+}
+
+/// Classify a decode fault, telling payload overruns apart from other truncation
+fn decode_issue(code: &[u8], ip: usize, fault: ProcessorFault) -> VerifyIssue {
+    match fault {
+        ProcessorFault::InvalidRegister => VerifyIssue::InvalidRegister,
+        ProcessorFault::TruncatedOperand => {
+            let wide = code[ip] == OpCode::Wide as u8;
+            let opcode = code.get(ip + wide as usize).copied().and_then(OpCode::from_u8);
+            if opcode.is_some_and(|op| op.operands().contains(&Operand::Payload)) {
+                VerifyIssue::PayloadOverrun
+            } else {
+                VerifyIssue::TruncatedOperand
+            }
+        }
+        _ => VerifyIssue::InvalidOpcode(code[ip]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assembled_programs_verify() {
+        let code = crate::compiler::assemble(r#"
+            loop:
+            SUBI 200 1
+            JUMPIF 200 loop
+            CALL done
+            SIGNAL 3 "ok"
+            done:
+            HALT
+        "#).unwrap();
+        let report = verify(&code);
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.instructions, 5);
+    }
+
+    #[test]
+    fn test_malformed_programs_are_reported() {
+        // JUMP into the middle of SET, an unknown opcode, then SIGNAL 3 "abc" cut short
+        let report = verify(&[0x10, 3, 0x01, 10, 20, 0xEE, 0xF0, 3, 3, b'a']);
+        let issues: Vec<_> = report.findings.iter().map(|f| (f.offset, f.issue)).collect();
+        assert_eq!(issues[0], (0, VerifyIssue::MisalignedTarget(3)));
+        assert_eq!(issues[1], (5, VerifyIssue::InvalidOpcode(0xEE)));
+        assert_eq!(issues[2], (6, VerifyIssue::PayloadOverrun));
+        assert!(!report.is_valid());
+
+        // Register operands are range-checked and targets must stay in the program
+        assert_eq!(verify(&[0x30, 9, 1]).findings[0].issue, VerifyIssue::InvalidRegister);
+        assert_eq!(verify(&[0x20, 200]).findings[0].issue, VerifyIssue::TargetOutOfRange(200));
+
+        // Code loaded higher up may only jump within itself
+        assert!(verify_at(&[0x10, 200], 200).is_valid());
+        assert_eq!(verify_at(&[0x10, 0], 200).findings[0], VerifyFinding { offset: 200, issue: VerifyIssue::TargetOutOfRange(0) });
+    }
+}
@@ -0,0 +1,63 @@
+//! Parala programs lowered by the compiler run correctly on the Universal ISA
+
+use paradox_kernel::universe::memory::PagePool;
+use paradox_kernel::universe::{EnergyCostModel, UniversalProcessor};
+use paradox_kernel::{StateVector, Universe, UniverseID};
+
+#[test]
+fn test_parala_arithmetic_runs() {
+    let source = r#"
+        universe calc {
+            a = 7 * 3;
+            b = a / 2;
+            c = a > b;
+            d = a == b;
+            e = 10 - 4;
+            f = a < b;
+        }
+    "#;
+    let mut code = parala_compiler::compile(source).unwrap();
+    code.resize(256, 0);
+    let mut u = Universe::new(UniverseID(1), 100.0);
+    u.state_vector = StateVector::new_raw(code);
+
+    run(&mut u);
+    assert_eq!(&u.state_vector.raw()[200..206], &[21, 10, 1, 0, 6, 0]);
+}
+
+#[test]
+fn test_parala_function_parameters() {
+    let source = r#"
+        universe calc {
+            func bump(x) {
+                return x + 1;
+            }
+            func diff(a, b) {
+                return a - b;
+            }
+            total = bump(41);
+            gap = diff(10, 3);
+        }
+    "#;
+    let mut code = parala_compiler::compile(source).unwrap();
+    code.resize(256, 0);
+    let mut u = Universe::new(UniverseID(1), 100.0);
+    u.state_vector = StateVector::new_raw(code);
+
+    run(&mut u);
+    // Variables in order of first use: x, a, b, total, gap
+    assert_eq!(&u.state_vector.raw()[200..205], &[41, 10, 3, 42, 7]);
+    assert!(u.stack.is_empty());
+}
+
+/// Step a universe until it halts, failing if it runs away
+fn run(u: &mut Universe) {
+    let (mut pool, costs) = (PagePool::new(), EnergyCostModel::default());
+    for _ in 0..10_000 {
+        if !u.is_running() {
+            return;
+        }
+        u.instruction_pointer = UniversalProcessor::step(u, &mut pool, &costs).unwrap().0;
+    }
+    panic!("program did not halt");
+}
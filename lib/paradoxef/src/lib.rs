@@ -0,0 +1,437 @@
+//! ParadoxEF - Executable Format
+//!
+//! Container for programs loaded into ParadoxOS universes, plus the
+//! relocatable objects (`object`) and linker (`link`) that build them from
+//! several sources. An image keeps code, read-only data, initialized data
+//! and zero-filled storage in separate sections, each placed at its own
+//! address in the universe's state vector, together with the entry point,
+//! the energy the program asks for, its call stack depth and a symbol
+//! table.
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! magic        [u8; 4]   0x7F 'P' 'X' 'E'
+//! version      u16
+//! entry        u16
+//! stack_size   u16
+//! energy       f64
+//! sections     u8 count, then per section: kind u8, addr u16, len u16, bytes
+//!              (BSS sections carry no bytes)
+//! symbols      u16 count, then per symbol: name_len u8, name, addr u16
+//! ```
+
+#![warn(missing_docs, rust_2018_idioms)]
+
+use thiserror::Error;
+
+pub mod link;
+pub mod object;
+
+pub use link::{link, LinkError};
+pub use object::{ObjectFile, ObjectSymbol, Relocation, RelocationKind, OBJECT_MAGIC};
+
+/// Magic bytes at the start of every image
+///
+/// 0x7F is not an opcode, so an image can never be mistaken for raw bytecode.
+pub const MAGIC: [u8; 4] = [0x7F, b'P', b'X', b'E'];
+
+/// Current format version
+pub const VERSION: u16 = 1;
+
+/// Size of the universe address space an image can describe
+pub const ADDRESS_SPACE: usize = 1 << 16;
+
+/// Image parsing and layout errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// Bytes do not start with the image magic
+    #[error("not a ParadoxEF image")]
+    BadMagic,
+
+    /// Image written by a newer format version
+    #[error("unsupported image version {0}")]
+    UnsupportedVersion(u16),
+
+    /// Image ends in the middle of a field
+    #[error("image truncated")]
+    Truncated,
+
+    /// Unknown section kind byte
+    #[error("unknown section kind {0}")]
+    BadSectionKind(u8),
+
+    /// Symbol name is not valid UTF-8 or too long
+    #[error("invalid symbol name")]
+    BadSymbol,
+
+    /// Energy request is negative, infinite or NaN
+    #[error("energy request is not a finite, non-negative amount")]
+    BadEnergy,
+
+    /// Section runs past the end of the address space
+    #[error("{kind} section at {addr} does not fit in the address space")]
+    SectionOutOfRange {
+        /// Section kind
+        kind: SectionKind,
+        /// Section start address
+        addr: u16,
+    },
+
+    /// Section is longer than its 16-bit length field
+    #[error("{kind} section is {len} bytes, at most 65535 fit")]
+    SectionTooLarge {
+        /// Section kind
+        kind: SectionKind,
+        /// Section length
+        len: usize,
+    },
+
+    /// Two sections claim the same address
+    #[error("sections overlap at address {0}")]
+    Overlap(usize),
+
+    /// Entry point is not inside a code section
+    #[error("entry point {0} is outside the code")]
+    EntryOutsideCode(u16),
+
+    /// A length or count is too large for the field that stores it
+    #[error("{what} is {len}, at most {max} fit")]
+    FieldOverflow {
+        /// What was being written
+        what: &'static str,
+        /// Its length or count
+        len: usize,
+        /// Largest value the field holds
+        max: usize,
+    },
+
+    /// Unknown relocation kind byte in an object
+    #[error("unknown relocation kind {0}")]
+    BadRelocationKind(u8),
+}
+
+/// Kind of an image section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum SectionKind {
+    /// Executable bytecode
+    Code = 0,
+    /// Constants the program only reads
+    Rodata = 1,
+    /// Initialized data
+    Data = 2,
+    /// Zero-initialized data (stored as a length only)
+    Bss = 3,
+}
+
+impl SectionKind {
+    /// All kinds, in conventional load order
+    pub const ALL: [SectionKind; 4] = [SectionKind::Code, SectionKind::Rodata, SectionKind::Data, SectionKind::Bss];
+
+    /// Decode a section kind byte
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        Self::ALL.get(byte as usize).copied()
+    }
+}
+
+impl std::fmt::Display for SectionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SectionKind::Code => "code",
+            SectionKind::Rodata => "rodata",
+            SectionKind::Data => "data",
+            SectionKind::Bss => "bss",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A contiguous block of the program placed at a fixed address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// What the section holds
+    pub kind: SectionKind,
+    /// Load address in the state vector
+    pub addr: u16,
+    /// Contents (all zero for BSS)
+    pub data: Vec<u8>,
+}
+
+impl Section {
+    /// Address one past the end of the section
+    pub fn end(&self) -> usize {
+        self.addr as usize + self.data.len()
+    }
+}
+
+/// A named address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Symbol name
+    pub name: String,
+    /// Address in the state vector
+    pub addr: u16,
+}
+
+/// A loadable program
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramImage {
+    /// Address execution starts at
+    pub entry: u16,
+    /// Call stack depth in bytes (0 = the loader's default)
+    pub stack_size: u16,
+    /// Energy the program asks its universe to hold (0 = no requirement)
+    pub energy: f64,
+    /// Sections, in file order
+    pub sections: Vec<Section>,
+    /// Symbol table
+    pub symbols: Vec<Symbol>,
+}
+
+impl ProgramImage {
+    /// Wrap raw bytecode as a single code section at address 0
+    pub fn from_code(code: Vec<u8>) -> Self {
+        Self {
+            entry: 0,
+            stack_size: 0,
+            energy: 0.0,
+            sections: vec![Section { kind: SectionKind::Code, addr: 0, data: code }],
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Check if bytes hold an image rather than raw bytecode
+    pub fn is_image(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    /// First section of a kind
+    pub fn section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|s| s.kind == kind)
+    }
+
+    /// Address of a symbol
+    pub fn symbol(&self, name: &str) -> Option<u16> {
+        self.symbols.iter().find(|s| s.name == name).map(|s| s.addr)
+    }
+
+    /// Serialize the image
+    ///
+    /// Fails if a section, name or table is too large for its field.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ImageError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.entry.to_le_bytes());
+        out.extend_from_slice(&self.stack_size.to_le_bytes());
+        out.extend_from_slice(&self.energy.to_le_bytes());
+
+        out.push(len_u8("section count", self.sections.len())?);
+        for section in &self.sections {
+            out.push(section.kind as u8);
+            out.extend_from_slice(&section.addr.to_le_bytes());
+            out.extend_from_slice(&section_len(section)?.to_le_bytes());
+            if section.kind != SectionKind::Bss {
+                out.extend_from_slice(&section.data);
+            }
+        }
+
+        out.extend_from_slice(&len_u16("symbol count", self.symbols.len())?.to_le_bytes());
+        for symbol in &self.symbols {
+            object::put_name(&mut out, &symbol.name)?;
+            out.extend_from_slice(&symbol.addr.to_le_bytes());
+        }
+        Ok(out)
+    }
+
+    /// Parse a serialized image
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        if !Self::is_image(bytes) {
+            return Err(ImageError::BadMagic);
+        }
+        let mut reader = Reader { bytes, pos: MAGIC.len() };
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(ImageError::UnsupportedVersion(version));
+        }
+        let entry = reader.u16()?;
+        let stack_size = reader.u16()?;
+        let energy = reader.energy()?;
+
+        let mut sections = Vec::new();
+        for _ in 0..reader.u8()? {
+            let kind = reader.section_kind()?;
+            let addr = reader.u16()?;
+            let len = reader.u16()? as usize;
+            let data = match kind {
+                SectionKind::Bss => vec![0; len],
+                _ => reader.take(len)?.to_vec(),
+            };
+            sections.push(Section { kind, addr, data });
+        }
+
+        let mut symbols = Vec::new();
+        for _ in 0..reader.u16()? {
+            let name = reader.name()?;
+            let addr = reader.u16()?;
+            symbols.push(Symbol { name, addr });
+        }
+
+        Ok(Self { entry, stack_size, energy, sections, symbols })
+    }
+
+    /// Lay the sections out as a flat memory image
+    ///
+    /// Gaps between sections are zero-filled. Fails if a section is too
+    /// long for its length field, sections overlap or the entry point is not
+    /// inside a code section.
+    pub fn layout(&self) -> Result<Vec<u8>, ImageError> {
+        let mut memory = Vec::new();
+        let mut claimed = Vec::new();
+        for section in &self.sections {
+            section_len(section)?;
+            if section.end() > ADDRESS_SPACE {
+                return Err(ImageError::SectionOutOfRange { kind: section.kind, addr: section.addr });
+            }
+            if memory.len() < section.end() {
+                memory.resize(section.end(), 0);
+                claimed.resize(section.end(), false);
+            }
+            let range = section.addr as usize..section.end();
+            if let Some(offset) = claimed[range.clone()].iter().position(|&c| c) {
+                return Err(ImageError::Overlap(section.addr as usize + offset));
+            }
+            claimed[range.clone()].fill(true);
+            memory[range].copy_from_slice(&section.data);
+        }
+
+        let entry = self.entry as usize;
+        let in_code = self.sections.iter()
+            .any(|s| s.kind == SectionKind::Code && (s.addr as usize..s.end()).contains(&entry));
+        if !in_code {
+            return Err(ImageError::EntryOutsideCode(self.entry));
+        }
+        Ok(memory)
+    }
+}
+
+/// Length of a section as stored in its 16-bit field
+pub(crate) fn section_len(section: &Section) -> Result<u16, ImageError> {
+    u16::try_from(section.data.len())
+        .map_err(|_| ImageError::SectionTooLarge { kind: section.kind, len: section.data.len() })
+}
+
+/// A length or count stored in a u8 field
+pub(crate) fn len_u8(what: &'static str, len: usize) -> Result<u8, ImageError> {
+    u8::try_from(len).map_err(|_| ImageError::FieldOverflow { what, len, max: u8::MAX as usize })
+}
+
+/// A length or count stored in a u16 field
+pub(crate) fn len_u16(what: &'static str, len: usize) -> Result<u16, ImageError> {
+    u16::try_from(len).map_err(|_| ImageError::FieldOverflow { what, len, max: u16::MAX as usize })
+}
+
+/// Cursor over image and object bytes
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ImageError> {
+        let bytes = self.bytes.get(self.pos..self.pos + len).ok_or(ImageError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ImageError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ImageError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Energy request, which must be a finite, non-negative amount
+    fn energy(&mut self) -> Result<f64, ImageError> {
+        let energy = f64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        match energy.is_finite() && energy >= 0.0 {
+            true => Ok(energy),
+            false => Err(ImageError::BadEnergy),
+        }
+    }
+
+    fn section_kind(&mut self) -> Result<SectionKind, ImageError> {
+        let kind = self.u8()?;
+        SectionKind::from_u8(kind).ok_or(ImageError::BadSectionKind(kind))
+    }
+
+    /// Length-prefixed UTF-8 name
+    fn name(&mut self) -> Result<String, ImageError> {
+        let len = self.u8()? as usize;
+        let name = std::str::from_utf8(self.take(len)?).map_err(|_| ImageError::BadSymbol)?;
+        Ok(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ProgramImage {
+        ProgramImage {
+            entry: 4,
+            stack_size: 16,
+            energy: 50.0,
+            sections: vec![
+                Section { kind: SectionKind::Code, addr: 0, data: vec![0x00, 0x00, 0x00, 0x00, 0xFF] },
+                Section { kind: SectionKind::Rodata, addr: 5, data: b"hi".to_vec() },
+                Section { kind: SectionKind::Bss, addr: 8, data: vec![0; 3] },
+            ],
+            symbols: vec![Symbol { name: "greeting".into(), addr: 5 }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = sample();
+        let bytes = image.to_bytes().unwrap();
+        assert!(ProgramImage::is_image(&bytes));
+        assert_eq!(ProgramImage::from_bytes(&bytes).unwrap(), image);
+        assert_eq!(image.symbol("greeting"), Some(5));
+
+        assert_eq!(ProgramImage::from_bytes(&bytes[..bytes.len() - 1]), Err(ImageError::Truncated));
+        assert_eq!(ProgramImage::from_bytes(&[0xFF]), Err(ImageError::BadMagic));
+        for energy in [f64::NAN, f64::INFINITY, -1.0] {
+            let mut bad = bytes.clone();
+            bad[10..18].copy_from_slice(&energy.to_le_bytes()); // After magic, version, entry and stack size
+            assert_eq!(ProgramImage::from_bytes(&bad), Err(ImageError::BadEnergy));
+        }
+
+        // Oversized fields are refused rather than truncated
+        let mut long = sample();
+        long.symbols[0].name = "x".repeat(256);
+        assert_eq!(long.to_bytes(), Err(ImageError::FieldOverflow { what: "name length", len: 256, max: 255 }));
+        let huge = ProgramImage::from_code(vec![0; ADDRESS_SPACE]);
+        assert!(matches!(huge.to_bytes(), Err(ImageError::SectionTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut image = sample();
+        assert_eq!(image.layout().unwrap(), vec![0, 0, 0, 0, 0xFF, b'h', b'i', 0, 0, 0, 0]);
+
+        image.entry = 5;
+        assert_eq!(image.layout(), Err(ImageError::EntryOutsideCode(5)));
+
+        image.entry = 0;
+        image.sections[2].addr = 6;
+        assert_eq!(image.layout(), Err(ImageError::Overlap(6)));
+
+        // The whole address space is one byte more than a section length holds
+        let image = ProgramImage::from_code(vec![0; ADDRESS_SPACE]);
+        assert_eq!(image.layout(), Err(ImageError::SectionTooLarge { kind: SectionKind::Code, len: ADDRESS_SPACE }));
+    }
+}
@@ -0,0 +1,277 @@
+//! Linker
+//!
+//! Combines relocatable objects into one loadable image. Sections of the
+//! same kind are concatenated in object order and placed like the
+//! assembler places a single source: code from address 0, then rodata,
+//! data and bss. Library objects are only pulled in when they define a
+//! symbol something already linked still needs, so a large library costs
+//! nothing for the routines a program does not call.
+
+use crate::object::{ObjectFile, RelocationKind};
+use crate::{ProgramImage, Section, SectionKind, Symbol, ADDRESS_SPACE};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Link errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    /// No object or library member defines a symbol
+    #[error("undefined symbol '{symbol}' referenced by {object}")]
+    UndefinedSymbol {
+        /// Missing symbol
+        symbol: String,
+        /// Object referencing it
+        object: String,
+    },
+
+    /// Two objects export the same symbol
+    #[error("symbol '{symbol}' is defined by both {first} and {second}")]
+    DuplicateSymbol {
+        /// Symbol name
+        symbol: String,
+        /// Object linked first
+        first: String,
+        /// Object linked second
+        second: String,
+    },
+
+    /// A relocated value does not fit its field
+    #[error("'{symbol}' resolves to {value} in {object}, which does not fit in {bits} bits")]
+    RelocationOverflow {
+        /// Symbol being relocated
+        symbol: String,
+        /// Resolved value
+        value: i64,
+        /// Field width
+        bits: usize,
+        /// Object holding the field
+        object: String,
+    },
+
+    /// A relocation points outside its section
+    #[error("relocation at {section} offset {offset} in {object} is outside the section")]
+    BadRelocation {
+        /// Section named by the relocation
+        section: SectionKind,
+        /// Offset named by the relocation
+        offset: u16,
+        /// Object holding the relocation
+        object: String,
+    },
+
+    /// An object asks for a negative, infinite or NaN amount of energy
+    #[error("{0} requests an energy that is not a finite, non-negative amount")]
+    BadEnergy(String),
+
+    /// Linked program does not fit in the address space
+    #[error("linked program needs {0} bytes, more than the 16-bit address space")]
+    TooLarge(usize),
+}
+
+/// Link `objects`, pulling in members of `library` as needed
+///
+/// The entry point is the first object's `.entry` symbol that any object
+/// sets, or the start of the code. Stack depth and energy requests take
+/// the largest value any linked object asks for.
+pub fn link(objects: &[ObjectFile], library: &[ObjectFile]) -> Result<ProgramImage, LinkError> {
+    let mut linked: Vec<&ObjectFile> = objects.iter().collect();
+    let mut globals: HashMap<&str, usize> = HashMap::new();
+    for index in 0..linked.len() {
+        export(&linked, index, &mut globals)?;
+    }
+
+    // Pull in library members until every reference is resolved
+    let mut next = 0;
+    while next < linked.len() {
+        let object = linked[next];
+        let references = object.imports.iter().chain(object.relocations.iter().map(|r| &r.symbol));
+        for symbol in references {
+            if object.symbol(symbol).is_some() || globals.contains_key(symbol.as_str()) {
+                continue;
+            }
+            let member = library.iter()
+                .find(|member| member.symbol(symbol).is_some_and(|s| s.global))
+                .ok_or_else(|| LinkError::UndefinedSymbol { symbol: symbol.clone(), object: object.name.clone() })?;
+            linked.push(member);
+            export(&linked, linked.len() - 1, &mut globals)?;
+        }
+        next += 1;
+    }
+    if let Some(object) = linked.iter().find(|o| !o.energy.is_finite() || o.energy < 0.0) {
+        return Err(LinkError::BadEnergy(object.name.clone()));
+    }
+
+    // Place sections kind by kind, object by object
+    let mut bases = vec![[0usize; 4]; linked.len()];
+    let mut merged: [Vec<u8>; 4] = Default::default();
+    let mut starts = [0usize; 4];
+    let mut end = 0;
+    for kind in SectionKind::ALL {
+        starts[kind as usize] = end;
+        for (index, object) in linked.iter().enumerate() {
+            bases[index][kind as usize] = end;
+            if let Some(section) = object.section(kind) {
+                merged[kind as usize].extend_from_slice(&section.data);
+                end += section.data.len();
+            }
+        }
+    }
+    if end > ADDRESS_SPACE {
+        return Err(LinkError::TooLarge(end));
+    }
+
+    let address = |index: usize, name: &str| -> Option<usize> {
+        let (index, symbol) = match linked[index].symbol(name) {
+            Some(symbol) => (index, symbol),
+            None => {
+                let owner = *globals.get(name)?;
+                (owner, linked[owner].symbol(name)?)
+            }
+        };
+        Some(bases[index][symbol.section as usize] + symbol.offset as usize)
+    };
+
+    for (index, object) in linked.iter().enumerate() {
+        for relocation in &object.relocations {
+            let target = address(index, &relocation.symbol).ok_or_else(|| LinkError::UndefinedSymbol {
+                symbol: relocation.symbol.clone(),
+                object: object.name.clone(),
+            })?;
+            let value = target as i64 + relocation.addend as i64;
+            let width = relocation.kind.width();
+            let max = match relocation.kind {
+                RelocationKind::Abs8 => u8::MAX as i64,
+                RelocationKind::Abs16 => u16::MAX as i64,
+                RelocationKind::Abs32 => u32::MAX as i64,
+            };
+            if !(0..=max).contains(&value) {
+                return Err(LinkError::RelocationOverflow {
+                    symbol: relocation.symbol.clone(),
+                    value,
+                    bits: width * 8,
+                    object: object.name.clone(),
+                });
+            }
+
+            let kind = relocation.section as usize;
+            let section_len = object.section(relocation.section).map_or(0, |s| s.data.len());
+            let offset = relocation.offset as usize;
+            if offset + width > section_len {
+                return Err(LinkError::BadRelocation {
+                    section: relocation.section,
+                    offset: relocation.offset,
+                    object: object.name.clone(),
+                });
+            }
+            let at = bases[index][kind] - starts[kind] + offset;
+            merged[kind][at..at + width].copy_from_slice(&(value as u32).to_le_bytes()[..width]);
+        }
+    }
+
+    let entry = linked.iter().enumerate()
+        .find_map(|(index, object)| {
+            let name = object.entry.as_ref()?;
+            Some(address(index, name).ok_or_else(|| LinkError::UndefinedSymbol {
+                symbol: name.clone(),
+                object: object.name.clone(),
+            }))
+        })
+        .transpose()?
+        .unwrap_or(0);
+
+    let mut symbols: Vec<Symbol> = globals.keys()
+        .map(|name| Symbol { name: name.to_string(), addr: address(globals[name], name).unwrap_or(0) as u16 })
+        .collect();
+    symbols.sort_by(|a, b| (a.addr, &a.name).cmp(&(b.addr, &b.name)));
+
+    Ok(ProgramImage {
+        entry: entry as u16,
+        stack_size: linked.iter().map(|o| o.stack_size).max().unwrap_or(0),
+        energy: linked.iter().map(|o| o.energy).fold(0.0, f64::max),
+        sections: SectionKind::ALL.into_iter()
+            .zip(merged)
+            .filter(|(kind, data)| *kind == SectionKind::Code || !data.is_empty())
+            .map(|(kind, data)| Section { kind, addr: starts[kind as usize] as u16, data })
+            .collect(),
+        symbols,
+    })
+}
+
+/// Register the global symbols of `linked[index]`
+fn export<'a>(linked: &[&'a ObjectFile], index: usize, globals: &mut HashMap<&'a str, usize>) -> Result<(), LinkError> {
+    for symbol in linked[index].symbols.iter().filter(|s| s.global) {
+        if let Some(&first) = globals.get(symbol.name.as_str()) {
+            return Err(LinkError::DuplicateSymbol {
+                symbol: symbol.name.clone(),
+                first: linked[first].name.clone(),
+                second: linked[index].name.clone(),
+            });
+        }
+        globals.insert(&symbol.name, index);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::{ObjectSymbol, Relocation};
+
+    fn object(name: &str, code: Vec<u8>, symbols: &[(&str, u16, bool)], relocations: &[(u16, &str, i32)]) -> ObjectFile {
+        ObjectFile {
+            name: name.into(),
+            entry: None,
+            stack_size: 0,
+            energy: 0.0,
+            sections: vec![Section { kind: SectionKind::Code, addr: 0, data: code }],
+            symbols: symbols.iter()
+                .map(|&(name, offset, global)| ObjectSymbol { name: name.into(), section: SectionKind::Code, offset, global })
+                .collect(),
+            imports: Vec::new(),
+            relocations: relocations.iter()
+                .map(|&(offset, symbol, addend)| Relocation {
+                    section: SectionKind::Code,
+                    offset,
+                    kind: RelocationKind::Abs8,
+                    symbol: symbol.into(),
+                    addend,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_link_resolves_across_objects() {
+        // main: CALL helper; JUMP main+2 / helper: RET
+        let mut main = object("main", vec![0x20, 0, 0x10, 0], &[("main", 0, true)], &[(1, "helper", 0), (3, "main", 2)]);
+        main.energy = 5.0;
+        let helper = object("helper", vec![0x21], &[("helper", 0, true)], &[]);
+        let unused = object("unused", vec![0xFF], &[("unused", 0, true)], &[]);
+
+        let image = link(&[main.clone()], &[unused, helper.clone()]).unwrap();
+        assert_eq!(image.sections[0].data, vec![0x20, 4, 0x10, 2, 0x21]);
+        assert_eq!(image.symbol("helper"), Some(4));
+        assert_eq!(image.symbol("unused"), None);
+        assert_eq!(image.energy, 5.0);
+
+        assert!(matches!(link(&[main.clone()], &[]), Err(LinkError::UndefinedSymbol { .. })));
+        assert!(matches!(link(&[main.clone(), main], &[helper]), Err(LinkError::DuplicateSymbol { .. })));
+    }
+
+    #[test]
+    fn test_relocation_overflow() {
+        let far = object("far", vec![0; 300], &[("far", 299, true)], &[]);
+        let near = object("near", vec![0x10, 0], &[], &[(1, "far", 0)]);
+        assert_eq!(
+            link(&[far, near], &[]).unwrap_err(),
+            LinkError::RelocationOverflow { symbol: "far".into(), value: 299, bits: 8, object: "near".into() }
+        );
+    }
+
+    #[test]
+    fn test_bad_energy_request() {
+        let mut main = object("main", vec![0xFF], &[], &[]);
+        main.energy = f64::NAN;
+        assert_eq!(link(&[main], &[]).unwrap_err(), LinkError::BadEnergy("main".into()));
+    }
+}
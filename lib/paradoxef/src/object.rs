@@ -0,0 +1,273 @@
+//! Relocatable objects
+//!
+//! An object holds one assembled source before linking. Sections start at
+//! offset 0 (`Section::addr` is unused), symbols are section offsets, and
+//! every operand that names a label or external symbol is left zero with a
+//! relocation telling the linker what to write there.
+//!
+//! ```text
+//! magic        [u8; 4]   0x7F 'P' 'X' 'O'
+//! version      u16
+//! name         name
+//! entry        name      (empty = none)
+//! stack_size   u16
+//! energy       f64
+//! sections     u8 count, then per section: kind u8, len u16, bytes
+//!              (BSS sections carry no bytes)
+//! symbols      u16 count, then per symbol: name, section u8, offset u16, global u8
+//! imports      u16 count, then names
+//! relocations  u16 count, then per relocation: section u8, offset u16,
+//!              kind u8, addend i32, symbol name
+//! ```
+//!
+//! Names are a u8 length followed by UTF-8 bytes.
+
+use crate::{len_u16, len_u8, section_len, ImageError, Reader, Section, SectionKind, VERSION};
+
+/// Magic bytes at the start of every object
+pub const OBJECT_MAGIC: [u8; 4] = [0x7F, b'P', b'X', b'O'];
+
+/// How a relocated value is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum RelocationKind {
+    /// One byte (narrow address or byte operand)
+    Abs8 = 0,
+    /// 16-bit little-endian (wide address, `.word`, 16-bit literal)
+    Abs16 = 1,
+    /// 32-bit little-endian (32-bit literal)
+    Abs32 = 2,
+}
+
+impl RelocationKind {
+    /// Decode a relocation kind byte
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Abs8),
+            1 => Some(Self::Abs16),
+            2 => Some(Self::Abs32),
+            _ => None,
+        }
+    }
+
+    /// Bytes the relocated value occupies
+    pub fn width(self) -> usize {
+        match self {
+            Self::Abs8 => 1,
+            Self::Abs16 => 2,
+            Self::Abs32 => 4,
+        }
+    }
+}
+
+/// A value the linker fills in once addresses are known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// Section holding the value
+    pub section: SectionKind,
+    /// Offset of the value within its section
+    pub offset: u16,
+    /// How the value is written
+    pub kind: RelocationKind,
+    /// Symbol whose address is written
+    pub symbol: String,
+    /// Constant added to the symbol's address
+    pub addend: i32,
+}
+
+/// A label defined by an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectSymbol {
+    /// Symbol name
+    pub name: String,
+    /// Section the label is in
+    pub section: SectionKind,
+    /// Offset within the section
+    pub offset: u16,
+    /// Visible to other objects (`.global`)
+    pub global: bool,
+}
+
+/// An assembled, not yet linked, source
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectFile {
+    /// Name of the source, for link errors
+    pub name: String,
+    /// Symbol execution starts at, if this object sets it
+    pub entry: Option<String>,
+    /// Call stack depth the object asks for (0 = no requirement)
+    pub stack_size: u16,
+    /// Energy the object asks for (0 = no requirement)
+    pub energy: f64,
+    /// Sections, each starting at offset 0
+    pub sections: Vec<Section>,
+    /// Labels the object defines
+    pub symbols: Vec<ObjectSymbol>,
+    /// Symbols the object expects other objects to define (`.extern`)
+    pub imports: Vec<String>,
+    /// Values to fill in at link time
+    pub relocations: Vec<Relocation>,
+}
+
+impl ObjectFile {
+    /// Check if bytes hold an object
+    pub fn is_object(bytes: &[u8]) -> bool {
+        bytes.starts_with(&OBJECT_MAGIC)
+    }
+
+    /// Section of a kind
+    pub fn section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|s| s.kind == kind)
+    }
+
+    /// A symbol this object defines
+    pub fn symbol(&self, name: &str) -> Option<&ObjectSymbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    /// Serialize the object
+    ///
+    /// Fails if a section, name or table is too large for its field.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ImageError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&OBJECT_MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        put_name(&mut out, &self.name)?;
+        put_name(&mut out, self.entry.as_deref().unwrap_or(""))?;
+        out.extend_from_slice(&self.stack_size.to_le_bytes());
+        out.extend_from_slice(&self.energy.to_le_bytes());
+
+        out.push(len_u8("section count", self.sections.len())?);
+        for section in &self.sections {
+            out.push(section.kind as u8);
+            out.extend_from_slice(&section_len(section)?.to_le_bytes());
+            if section.kind != SectionKind::Bss {
+                out.extend_from_slice(&section.data);
+            }
+        }
+
+        out.extend_from_slice(&len_u16("symbol count", self.symbols.len())?.to_le_bytes());
+        for symbol in &self.symbols {
+            put_name(&mut out, &symbol.name)?;
+            out.push(symbol.section as u8);
+            out.extend_from_slice(&symbol.offset.to_le_bytes());
+            out.push(symbol.global as u8);
+        }
+
+        out.extend_from_slice(&len_u16("import count", self.imports.len())?.to_le_bytes());
+        for import in &self.imports {
+            put_name(&mut out, import)?;
+        }
+
+        out.extend_from_slice(&len_u16("relocation count", self.relocations.len())?.to_le_bytes());
+        for relocation in &self.relocations {
+            out.push(relocation.section as u8);
+            out.extend_from_slice(&relocation.offset.to_le_bytes());
+            out.push(relocation.kind as u8);
+            out.extend_from_slice(&relocation.addend.to_le_bytes());
+            put_name(&mut out, &relocation.symbol)?;
+        }
+        Ok(out)
+    }
+
+    /// Parse a serialized object
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        if !Self::is_object(bytes) {
+            return Err(ImageError::BadMagic);
+        }
+        let mut reader = Reader { bytes, pos: OBJECT_MAGIC.len() };
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(ImageError::UnsupportedVersion(version));
+        }
+        let name = reader.name()?;
+        let entry = Some(reader.name()?).filter(|entry| !entry.is_empty());
+        let stack_size = reader.u16()?;
+        let energy = reader.energy()?;
+
+        let mut sections = Vec::new();
+        for _ in 0..reader.u8()? {
+            let kind = reader.section_kind()?;
+            let len = reader.u16()? as usize;
+            let data = match kind {
+                SectionKind::Bss => vec![0; len],
+                _ => reader.take(len)?.to_vec(),
+            };
+            sections.push(Section { kind, addr: 0, data });
+        }
+
+        let mut symbols = Vec::new();
+        for _ in 0..reader.u16()? {
+            let name = reader.name()?;
+            let section = reader.section_kind()?;
+            let offset = reader.u16()?;
+            let global = reader.u8()? != 0;
+            symbols.push(ObjectSymbol { name, section, offset, global });
+        }
+
+        let mut imports = Vec::new();
+        for _ in 0..reader.u16()? {
+            imports.push(reader.name()?);
+        }
+
+        let mut relocations = Vec::new();
+        for _ in 0..reader.u16()? {
+            let section = reader.section_kind()?;
+            let offset = reader.u16()?;
+            let kind = reader.u8()?;
+            let kind = RelocationKind::from_u8(kind).ok_or(ImageError::BadRelocationKind(kind))?;
+            let addend = i32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+            let symbol = reader.name()?;
+            relocations.push(Relocation { section, offset, kind, symbol, addend });
+        }
+
+        Ok(Self { name, entry, stack_size, energy, sections, symbols, imports, relocations })
+    }
+}
+
+/// Write a length-prefixed name
+pub(crate) fn put_name(out: &mut Vec<u8>, name: &str) -> Result<(), ImageError> {
+    out.push(len_u8("name length", name.len())?);
+    out.extend_from_slice(name.as_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_round_trip() {
+        let object = ObjectFile {
+            name: "main.asm".into(),
+            entry: Some("main".into()),
+            stack_size: 0,
+            energy: 10.0,
+            sections: vec![
+                Section { kind: SectionKind::Code, addr: 0, data: vec![0xFE, 0x20, 0, 0, 0xFF] },
+                Section { kind: SectionKind::Bss, addr: 0, data: vec![0; 4] },
+            ],
+            symbols: vec![ObjectSymbol { name: "main".into(), section: SectionKind::Code, offset: 0, global: true }],
+            imports: vec!["memcpy".into()],
+            relocations: vec![Relocation {
+                section: SectionKind::Code,
+                offset: 2,
+                kind: RelocationKind::Abs16,
+                symbol: "memcpy".into(),
+                addend: -1,
+            }],
+        };
+        let bytes = object.to_bytes().unwrap();
+        assert!(ObjectFile::is_object(&bytes));
+        assert!(!crate::ProgramImage::is_image(&bytes));
+        assert_eq!(ObjectFile::from_bytes(&bytes).unwrap(), object);
+        assert_eq!(ObjectFile::from_bytes(&bytes[..bytes.len() - 1]), Err(ImageError::Truncated));
+        let mut bad = object.clone();
+        bad.energy = f64::NAN;
+        assert_eq!(ObjectFile::from_bytes(&bad.to_bytes().unwrap()), Err(ImageError::BadEnergy));
+
+        let mut long = object;
+        long.imports[0] = "m".repeat(300);
+        assert_eq!(long.to_bytes(), Err(ImageError::FieldOverflow { what: "name length", len: 300, max: 255 }));
+    }
+}
@@ -35,7 +35,17 @@
 
 // --- Memory Map Constants ---
 #define PARADOX_REG_BASE 200
-#define PARADOX_STACK_TOP 255
+// The call stack lives outside RAM; images may request another depth
+#define PARADOX_STACK_DEPTH 256
+
+// --- SELF / SELFR Field Codes (fixed-point values are in thousandths) ---
+#define PARADOX_SELF_ENERGY      0
+#define PARADOX_SELF_ENTROPY     1
+#define PARADOX_SELF_STABILITY   2
+#define PARADOX_SELF_ID          3
+#define PARADOX_SELF_TIMELINE    4
+#define PARADOX_SELF_BUDGET      5
+#define PARADOX_SELF_INBOX_DEPTH 6
 
 // --- High Level API ---
 
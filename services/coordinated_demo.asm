@@ -7,22 +7,22 @@
 # 3. Router processes and notifies Monitor
 # 4. Monitor tracks completion
 
+.include "prelude.inc"
+
 .def work_id 200
 .def status 201
 .def temp 202
 
 # Submit work request
 SET work_id 42
-SET status 0
+clear status
 
 # Send work to Scheduler (Universe 4)
 SIGNAL 4 "WorkRequest:42"
 
 # Wait for processing (simulate)
 SET temp 10
-wait_loop:
-    SUB temp 1
-    JUMPIF temp wait_loop
+countdown temp
 
 # Check status from Monitor
 SIGNAL 6 "StatusQuery"
@@ -8,6 +8,8 @@
 # 21:      Alert threshold
 # 200-210: Working registers
 
+.include "prelude.inc"
+
 .def health_start 10
 .def check_counter 20
 .def alert_threshold 21
@@ -15,12 +17,12 @@
 .def current_health 201
 
 # Initialize
-SET check_counter 0
+clear check_counter
 SET alert_threshold 5
 
 monitor_loop:
     # Increment check counter
-    ADD check_counter 1
+    ADDI check_counter 1
     
     # Check Universe 3 health (example)
     SET current_health 10  # Address for U3 health
@@ -29,13 +31,11 @@ monitor_loop:
     # In real version would check actual stability scores
     
     # Send periodic health report
-    CMP check_counter alert_threshold temp
-    JUMPIF temp send_alert
-    JUMP continue_monitor
+    if_cmp check_counter alert_threshold temp send_alert continue_monitor
     
 send_alert:
     SIGNAL 1 "Health Check"
-    SET check_counter 0
+    clear check_counter
     
 continue_monitor:
     # Check next universe
@@ -1,60 +1,31 @@
 # ParadoxOS Message Router
-# Routes signals between universes with priority queuing
+# Receives signals from the inbox and acknowledges each routed message
 #
 # Memory Layout:
-# 0-9:     Code section
-# 10-59:   Message queue (5 slots x 10 bytes each)
-# 60:      Queue head
-# 61:      Queue tail
-# 62:      Queue count
-# 200-210: Working registers
+# 200-201: Message source universe
+# 202:     Message type (0 = inbox empty)
+# 203:     Payload length
+# 204-235: Message payload
+# 236:     Routed message counter
 
-.def queue_start 10
-.def queue_head 60
-.def queue_tail 61
-.def queue_count 62
-.def max_queue 5
-.def temp 200
-.def msg_energy 201
+.include "prelude.inc"
 
-# Initialize
-SET queue_head 0
-SET queue_tail 0
-SET queue_count 0
+.def msg 200
+.def msg_type 202
+.def max_payload 32
+.def routed 236
+
+clear routed
 
 router_loop:
-    # Check for incoming messages (simulated)
-    # In real implementation, would receive signals
-    
-    # Check if queue has messages
-    CMP queue_count 0 temp
-    JUMPIF temp route_message
-    
-    # Queue empty - send idle signal
-    SIGNAL 1 "Router Idle"
+    # Sleep until a message arrives (no energy is spent while blocked)
+    WAIT 0
+    RECV msg max_payload
+    JUMPIF msg_type route_message
     JUMP router_loop
 
 route_message:
-    # Get message from queue head
-    SET temp queue_start
-    ADD temp queue_head
-    
-    # Process message (simplified: just forward it)
-    # In real version would read destination from memory
-    
-    # Decrement queue count
-    SUB queue_count 1
-    
-    # Advance head pointer
-    ADD queue_head 1
-    CMP queue_head max_queue temp
-    JUMPIF temp reset_head
-    JUMP continue_routing
-    
-reset_head:
-    SET queue_head 0
-    
-continue_routing:
+    ADDI routed 1
     SIGNAL 1 "Message Routed"
     JUMP router_loop
 
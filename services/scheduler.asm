@@ -9,6 +9,8 @@
 # 22:      Loop counter
 # 200-210: Working registers
 
+.include "prelude.inc"
+
 .def queue_start 10
 .def queue_len 20
 .def current_idx 21
@@ -16,13 +18,13 @@
 .def temp 200
 
 # Initialize
-SET queue_len 0
-SET current_idx 0
-SET loop_counter 0
+clear queue_len
+clear current_idx
+clear loop_counter
 
 scheduler_loop:
     # Check if we have any processes
-    CMP queue_len 0 temp
+    CMPI queue_len 0 temp
     JUMPIF temp end_scheduler
     
     # Get current process ID from queue
@@ -35,19 +37,17 @@ scheduler_loop:
     # For now, hard-code a few known universes
     
     # Advance to next process
-    ADD current_idx 1
+    ADDI current_idx 1
     
     # Wrap around if needed
-    CMP current_idx queue_len temp
-    JUMPIF temp reset_idx
-    JUMP continue_loop
+    if_cmp current_idx queue_len temp reset_idx continue_loop
     
 reset_idx:
-    SET current_idx 0
+    clear current_idx
     
 continue_loop:
     # Small delay (just increment counter)
-    ADD loop_counter 1
+    ADDI loop_counter 1
     
     # Send heartbeat signal
     SIGNAL 1 "Scheduler Active"
@@ -3,22 +3,24 @@
 #
 # This program counts and reports its progress
 
+.include "prelude.inc"
+
 .def counter 200
 .def max_count 10
 .def temp 201
 
 # Initialize
-SET counter 0
+clear counter
 
 work_loop:
     # Do some "work"
-    ADD counter 1
+    ADDI counter 1
     
     # Report progress
     SIGNAL 1 "Working"
     
     # Check if done
-    CMP counter max_count temp
+    CMPI counter max_count temp
     JUMPIF temp work_done
     
     # Continue working